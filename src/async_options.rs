@@ -0,0 +1,79 @@
+use std::{future::Future, rc::Rc, time::Duration};
+
+use floem::{
+    reactive::{create_effect, create_rw_signal, ReadSignal},
+    view::View,
+};
+
+use crate::{
+    combobox::{combobox_with, ComboValue, ComboboxOptions, ListStatus, OptionSource},
+    dropdown::DropDownStyles,
+    floem_tokio::create_resource,
+    gesture::TimerSlot,
+    style::DesignSystem,
+};
+
+pub const DEFAULT_QUERY_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// A combobox whose options are fetched for the typed text, e.g. from a backend search. The text
+/// has to rest for `debounce` before `fetch` runs on the tokio runtime. The list shows a loading
+/// row while a fetch is running and the error of a failed one; options are shown in the order
+/// `fetch` returns them.
+pub fn async_combobox<DS, T, Fu>(
+    fetch: impl Fn(String) -> Fu + Send + Sync + 'static,
+    label_fn: impl Fn(&T) -> String + Copy + 'static,
+    on_select: impl Fn(ComboValue<T>) + Copy + 'static,
+    debounce: Duration,
+    options: ComboboxOptions,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    T: Clone + Send + 'static,
+    Fu: Future<Output = Result<Vec<T>, String>> + Send + 'static,
+{
+    let query = create_rw_signal(String::new());
+    let debounced = create_rw_signal(String::new());
+    let timer = TimerSlot::default();
+    create_effect(move |_| {
+        let text = query.get();
+        timer.start(debounce, move || {
+            if debounced.with_untracked(|current| current != &text) {
+                debounced.set(text);
+            }
+        });
+    });
+
+    let resource = create_resource(move || debounced.get(), fetch);
+    // the resource only fetches on changes, so poke it once for the initial options
+    debounced.set(String::new());
+
+    let items = create_rw_signal(im::Vector::new());
+    create_effect(move |_| match resource.read() {
+        Some(Ok(list)) => items.set(list.into_iter().collect()),
+        Some(Err(_)) => items.set(im::Vector::new()),
+        None => {}
+    });
+    let status = move || {
+        if resource.loading() {
+            return Some(ListStatus::Loading);
+        }
+        match resource.read() {
+            Some(Err(error)) => Some(ListStatus::Error(error)),
+            _ => None,
+        }
+    };
+
+    combobox_with::<DS, T>(
+        items.read_only(),
+        query,
+        create_rw_signal(String::new()),
+        OptionSource::Remote {
+            status: Rc::new(status),
+        },
+        label_fn,
+        on_select,
+        options,
+        styles,
+    )
+}
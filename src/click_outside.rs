@@ -0,0 +1,209 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use floem::{
+    action::{add_overlay, remove_overlay},
+    event::{Event, EventListener},
+    id::Id,
+    kurbo::{Point, Rect, Size},
+    view::View,
+    views::{empty, Decorators},
+    EventPropagation,
+};
+
+/// The window-space bounds of a view, kept up to date from `on_move` and `on_resize`.
+#[derive(Clone, Default)]
+pub(crate) struct WindowBounds {
+    origin: Rc<Cell<Point>>,
+    size: Rc<Cell<Size>>,
+}
+impl WindowBounds {
+    pub(crate) fn rect(&self) -> Rect {
+        Rect::from_origin_size(self.origin.get(), self.size.get())
+    }
+
+    pub(crate) fn track<V: Decorators>(&self, view: V) -> V {
+        let (origin, size) = (self.origin.clone(), self.size.clone());
+        view.on_move(move |point| origin.set(point))
+            .on_resize(move |rect| size.set(rect.size()))
+    }
+}
+
+struct Entry {
+    id: Id,
+    /// Clicks inside an owned view count as clicks inside its owner
+    owner: Option<Id>,
+    bounds: WindowBounds,
+    handler: Option<Rc<dyn Fn()>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    entries: Vec<Entry>,
+    catcher: Option<Id>,
+}
+impl Registry {
+    fn contains(&self, id: Id, point: Point) -> bool {
+        // owner links come from user code, so guard against cycles
+        let mut visited = Vec::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if visited.contains(&id) {
+                continue;
+            }
+            visited.push(id);
+            for entry in &self.entries {
+                if entry.id == id && entry.bounds.rect().contains(point) {
+                    return true;
+                }
+                if entry.owner == Some(id) {
+                    stack.push(entry.id);
+                }
+            }
+        }
+        false
+    }
+}
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default());
+}
+
+fn register(entry: Entry) {
+    let needs_catcher = REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        registry.entries.push(entry);
+        registry.catcher.is_none()
+    });
+    if needs_catcher {
+        // A full window overlay that only observes pointer downs and lets them continue to the
+        // views below it. It is added lazily so apps don't have to wire anything up.
+        let catcher = add_overlay(Point::ZERO, |_| {
+            empty()
+                .style(|s| s.size_full())
+                .on_event(EventListener::PointerDown, |event| {
+                    if let Event::PointerDown(pointer) = event {
+                        dispatch(pointer.pos);
+                    }
+                    EventPropagation::Continue
+                })
+        });
+        REGISTRY.with(|registry| registry.borrow_mut().catcher = Some(catcher));
+    }
+}
+
+fn unregister(id: Id) {
+    let catcher = REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        registry.entries.retain(|entry| entry.id != id);
+        if registry.entries.is_empty() {
+            registry.catcher.take()
+        } else {
+            None
+        }
+    });
+    if let Some(catcher) = catcher {
+        remove_overlay(catcher);
+    }
+}
+
+fn dispatch(point: Point) {
+    let handlers: Vec<Rc<dyn Fn()>> = REGISTRY.with(|registry| {
+        let registry = registry.borrow();
+        registry
+            .entries
+            .iter()
+            .filter(|entry| !registry.contains(entry.id, point))
+            .filter_map(|entry| entry.handler.clone())
+            .collect()
+    });
+    // run after releasing the registry so handlers can close views and unregister them
+    for handler in handlers {
+        handler();
+    }
+}
+
+pub trait ExtClickOutside {
+    /// Run `handler` when the pointer goes down anywhere outside this view and the views it owns.
+    fn on_click_outside(self, handler: impl Fn() + 'static) -> Self;
+
+    /// Treat clicks inside this view as inside `owner`. Overlays such as popovers and dropdown
+    /// lists call this with their anchor so opening them doesn't count as an outside click.
+    fn click_outside_owner(self, owner: Id) -> Self;
+}
+impl<T: View + Decorators> ExtClickOutside for T {
+    fn on_click_outside(self, handler: impl Fn() + 'static) -> Self {
+        track(self, None, Some(Rc::new(handler)))
+    }
+
+    fn click_outside_owner(self, owner: Id) -> Self {
+        track(self, Some(owner), None)
+    }
+}
+
+/// Free function form of [`ExtClickOutside::on_click_outside`].
+pub fn on_click_outside<V: View + Decorators>(view: V, handler: impl Fn() + 'static) -> V {
+    view.on_click_outside(handler)
+}
+
+fn track<V: View + Decorators>(view: V, owner: Option<Id>, handler: Option<Rc<dyn Fn()>>) -> V {
+    let id = view.id();
+    let bounds = WindowBounds::default();
+    register(Entry {
+        id,
+        owner,
+        bounds: bounds.clone(),
+        handler,
+    });
+    bounds.track(view).on_cleanup(move || unregister(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: Id, owner: Option<Id>, rect: Rect) -> Entry {
+        let bounds = WindowBounds::default();
+        bounds.origin.set(rect.origin());
+        bounds.size.set(rect.size());
+        Entry {
+            id,
+            owner,
+            bounds,
+            handler: None,
+        }
+    }
+
+    #[test]
+    fn owned_views_count_as_inside() {
+        let (menu, submenu) = (Id::next(), Id::next());
+        let registry = Registry {
+            entries: vec![
+                entry(menu, None, Rect::new(0., 0., 100., 100.)),
+                entry(submenu, Some(menu), Rect::new(100., 0., 200., 100.)),
+            ],
+            catcher: None,
+        };
+
+        assert!(registry.contains(menu, Point::new(150., 50.)));
+        assert!(!registry.contains(submenu, Point::new(50., 50.)));
+        assert!(!registry.contains(menu, Point::new(250., 50.)));
+    }
+
+    #[test]
+    fn owner_cycle_terminates() {
+        let (a, b) = (Id::next(), Id::next());
+        let registry = Registry {
+            entries: vec![
+                entry(a, Some(b), Rect::new(0., 0., 10., 10.)),
+                entry(b, Some(a), Rect::new(20., 0., 30., 10.)),
+            ],
+            catcher: None,
+        };
+
+        assert!(registry.contains(a, Point::new(25., 5.)));
+        assert!(!registry.contains(a, Point::new(50., 5.)));
+    }
+}
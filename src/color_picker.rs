@@ -0,0 +1,452 @@
+use floem::{
+    event::{Event, EventListener},
+    keyboard::{Key, NamedKey},
+    kurbo::Size,
+    peniko::Color,
+    reactive::{create_effect, create_memo, create_rw_signal, RwSignal},
+    style::{Background, BorderColor, Position, TextColor},
+    view::View,
+    views::{container, empty, h_stack, label, list, text_input, v_stack, Decorators},
+    EventPropagation,
+};
+
+use crate::{
+    click_outside::ExtClickOutside,
+    gesture::ExtGesture,
+    pop_over::{pop_over, Align, Placement, Side},
+    style::{lazy_size, DesignSystem, ExtDynamicColor, HSLColor, LightDark},
+};
+
+pub const MAX_RECENT_COLORS: usize = 10;
+
+const PLANE_WIDTH: f64 = 192.;
+const PLANE_HEIGHT: f64 = 128.;
+const PLANE_COLUMNS: usize = 24;
+const PLANE_ROWS: usize = 16;
+const TRACK_HEIGHT: f64 = 12.;
+const TRACK_CELLS: usize = 32;
+const THUMB: f64 = 10.;
+const SWATCH: f64 = 16.;
+
+/// Move `color` to the front of `recents`, keeping at most [`MAX_RECENT_COLORS`].
+pub fn push_recent_color(recents: RwSignal<im::Vector<HSLColor>>, color: HSLColor) {
+    recents.update(|recents| {
+        recents.retain(|recent| recent != &color);
+        recents.push_front(color);
+        recents.truncate(MAX_RECENT_COLORS);
+    });
+}
+
+/// Calls `on_pick` with the pointer position as a fraction of the view's size, on press and
+/// while dragging.
+fn pickable<V: View + 'static>(view: V, on_pick: impl Fn(f64, f64) + Copy + 'static) -> V {
+    let (size_fn, size) = lazy_size();
+    let pick = move |x: f64, y: f64| {
+        let Size { width, height } = size();
+        if width > 0. && height > 0. {
+            on_pick((x / width).clamp(0., 1.), (y / height).clamp(0., 1.));
+        }
+    };
+    view.on_resize(size_fn)
+        .on_event(EventListener::PointerDown, move |event| {
+            if let Some(pos) = event.point() {
+                pick(pos.x, pos.y);
+            }
+            EventPropagation::Continue
+        })
+        .on_pan(0., move |pan| pick(pan.pos.x, pan.pos.y))
+}
+
+fn thumb(left: impl Fn() -> f64 + 'static, top: impl Fn() -> f64 + 'static) -> impl View {
+    empty().style(move |s| {
+        s.position(Position::Absolute)
+            .inset_left(left() - THUMB / 2.)
+            .inset_top(top() - THUMB / 2.)
+            .size(THUMB, THUMB)
+            .border(2.)
+            .border_radius(THUMB / 2.)
+            .border_color(Color::WHITE)
+    })
+}
+
+/// A horizontal strip of `TRACK_CELLS` colors with a thumb at `value`, 0-1.
+fn track(
+    cell_color: impl Fn(usize) -> HSLColor + Copy + 'static,
+    value: impl Fn() -> f64 + Copy + 'static,
+    on_change: impl Fn(f64) + Copy + 'static,
+) -> impl View {
+    let cell_width = PLANE_WIDTH / TRACK_CELLS as f64;
+    pickable(
+        v_stack((
+            list(
+                move || 0..TRACK_CELLS,
+                |idx| *idx,
+                move |idx| {
+                    empty().style(move |s| {
+                        s.size(cell_width, TRACK_HEIGHT)
+                            .background(cell_color(idx).color())
+                    })
+                },
+            )
+            .style(|s| s.flex_row()),
+            thumb(move || value() * PLANE_WIDTH, || TRACK_HEIGHT / 2.),
+        )),
+        move |x, _| on_change(x),
+    )
+    .style(|s| s.size(PLANE_WIDTH, TRACK_HEIGHT))
+}
+
+fn fraction(idx: usize, count: usize) -> f64 {
+    idx as f64 / (count - 1) as f64
+}
+
+fn percent(fraction: f64) -> u8 {
+    (fraction * 100.).round() as u8
+}
+
+fn parse_numbers(text: &str) -> Vec<Option<f64>> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.trim_end_matches(['%', '°']).parse().ok())
+        .collect()
+}
+
+fn format_rgb(color: HSLColor) -> String {
+    let (red, green, blue) = color.to_rgb();
+    format!("{red}, {green}, {blue}")
+}
+
+fn parse_rgb(text: &str, current: HSLColor) -> Option<HSLColor> {
+    let channel = |val: Option<f64>| val.filter(|val| (0. ..=255.).contains(val));
+    match parse_numbers(text)[..] {
+        [red, green, blue] => Some(HSLColor::from_rgb(
+            channel(red)? as u8,
+            channel(green)? as u8,
+            channel(blue)? as u8,
+            current.alpha(),
+        )),
+        _ => None,
+    }
+}
+
+fn format_hsl(color: HSLColor) -> String {
+    let degrees = (color.hue() as f64 * 3.6).round();
+    format!("{degrees}°, {}%, {}%", color.sat(), color.light())
+}
+
+fn parse_hsl(text: &str, current: HSLColor) -> Option<HSLColor> {
+    let within = |val: Option<f64>, max: f64| val.filter(|val| (0. ..=max).contains(val));
+    match parse_numbers(text)[..] {
+        [hue, sat, light] => Some(HSLColor::new(
+            (within(hue, 360.)? / 3.6).round() as u8,
+            within(sat, 100.)?.round() as u8,
+            within(light, 100.)?.round() as u8,
+            current.alpha(),
+        )),
+        _ => None,
+    }
+}
+
+fn parse_hex(text: &str, current: HSLColor) -> Option<HSLColor> {
+    let color = HSLColor::from_hex(text)?;
+    // without an alpha channel keep the current one
+    if text.trim().trim_start_matches('#').len() <= 6 {
+        Some(color.set_alpha(current.alpha()))
+    } else {
+        Some(color)
+    }
+}
+
+/// A labelled text input for `color`, committed on Enter or when focus leaves.
+fn color_field<DS: DesignSystem + 'static>(
+    name: &'static str,
+    color: RwSignal<HSLColor>,
+    format: fn(HSLColor) -> String,
+    parse: fn(&str, HSLColor) -> Option<HSLColor>,
+) -> impl View {
+    let text = create_rw_signal(format(color.get_untracked()));
+    let invalid = create_rw_signal(false);
+    create_effect(move |_| text.set(format(color.get())));
+    create_effect(move |_| {
+        let parsed = text.with(|text| parse(text, color.get_untracked()));
+        invalid.set(parsed.is_none());
+    });
+    let commit = move || match text.with_untracked(|text| parse(text, color.get_untracked())) {
+        Some(new) => color.set(new),
+        None => text.set(format(color.get_untracked())),
+    };
+
+    h_stack((
+        label(move || name).style(|s| s.width(36.).ld_color(TextColor, DS::TEXT.base)),
+        text_input(text)
+            .keyboard_navigatable()
+            .on_event(EventListener::KeyDown, move |event| {
+                let Event::KeyDown(key_event) = event else {
+                    return EventPropagation::Continue;
+                };
+                match &key_event.key.logical_key {
+                    Key::Named(NamedKey::Enter) => commit(),
+                    Key::Named(NamedKey::Escape) => text.set(format(color.get_untracked())),
+                    _ => return EventPropagation::Continue,
+                }
+                EventPropagation::Stop
+            })
+            .on_event(EventListener::FocusLost, move |_| {
+                commit();
+                EventPropagation::Continue
+            })
+            .style(move |s| {
+                s.flex_grow(1.)
+                    .padding_horiz(DS::PADDING / 2.)
+                    .border(DS::BORDER_SMALL)
+                    .border_radius(DS::BORDER_RADIUS)
+                    .ld_color(BorderColor, DS::BACKGROUND_3.base)
+                    .apply_if(invalid.get(), |s| s.ld_color(BorderColor, DS::DANGER.base))
+            }),
+    ))
+    .style(|s| s.items_center().width_full())
+}
+
+fn swatch(fill: impl Fn() -> HSLColor + 'static, on_click: impl Fn() + 'static) -> impl View {
+    empty().on_click_stop(move |_| on_click()).style(move |s| {
+        s.size(SWATCH, SWATCH)
+            .border_radius(SWATCH / 4.)
+            .background(fill().color())
+    })
+}
+
+/// A saturation/lightness plane with hue and alpha sliders below, hex/rgb/hsl inputs and
+/// swatches of the `DS` colors and of `recents`. Edits go straight to `color`; see
+/// [`color_button`] for a picker in a popover that also records recent colors.
+pub fn color_picker<DS: DesignSystem + 'static>(
+    color: RwSignal<HSLColor>,
+    recents: RwSignal<im::Vector<HSLColor>>,
+) -> impl View {
+    // the plane only needs repainting when the hue changes
+    let hue = create_memo(move |_| color.with(|color| color.hue()));
+    let cell_size = (
+        PLANE_WIDTH / PLANE_COLUMNS as f64,
+        PLANE_HEIGHT / PLANE_ROWS as f64,
+    );
+
+    let cells = list(
+        move || 0..PLANE_ROWS,
+        |row| *row,
+        move |row| {
+            list(
+                move || 0..PLANE_COLUMNS,
+                |col| *col,
+                move |col| {
+                    empty().style(move |s| {
+                        let sat = percent(fraction(col, PLANE_COLUMNS));
+                        let light = 100 - percent(fraction(row, PLANE_ROWS));
+                        s.size(cell_size.0, cell_size.1)
+                            .background(HSLColor::new(hue.get(), sat, light, 100).color())
+                    })
+                },
+            )
+            .style(|s| s.flex_row())
+        },
+    )
+    .style(|s| s.flex_col());
+    let plane = pickable(
+        v_stack((
+            cells,
+            thumb(
+                move || color.with(|color| color.sat()) as f64 / 100. * PLANE_WIDTH,
+                move || (100 - color.with(|color| color.light())) as f64 / 100. * PLANE_HEIGHT,
+            ),
+        )),
+        move |x, y| {
+            color.update(|color| {
+                *color = HSLColor::new(color.hue(), percent(x), 100 - percent(y), color.alpha())
+            })
+        },
+    )
+    .style(|s| s.size(PLANE_WIDTH, PLANE_HEIGHT));
+
+    let hue_track = track(
+        |idx| HSLColor::new(percent(fraction(idx, TRACK_CELLS)), 100, 50, 100),
+        move || hue.get() as f64 / 100.,
+        move |x| {
+            color.update(|color| {
+                *color = HSLColor::new(percent(x), color.sat(), color.light(), color.alpha())
+            })
+        },
+    );
+    let alpha_track = track(
+        move |idx| color.get().set_alpha(percent(fraction(idx, TRACK_CELLS))),
+        move || color.with(|color| color.alpha()) as f64 / 100.,
+        move |x| color.update(|color| *color = color.set_alpha(percent(x))),
+    );
+
+    let theme = [
+        DS::PRIMARY.base,
+        DS::SECONDARY.base,
+        DS::ACCENT.base,
+        DS::SUCCESS.base,
+        DS::WARNING.base,
+        DS::DANGER.base,
+        DS::TEXT.base,
+        DS::BACKGROUND.base,
+    ];
+    let theme_swatches = list(
+        move || theme.into_iter().enumerate(),
+        |(idx, _)| *idx,
+        move |(_, ld): (usize, LightDark)| {
+            swatch(move || ld.get_base(), move || color.set(ld.get_base()))
+        },
+    )
+    .style(|s| s.flex_row().gap(4., 0.));
+    let recent_swatches = list(
+        move || recents.get().into_iter().enumerate(),
+        |(idx, recent)| (*idx, recent.to_hex()),
+        move |(_, recent)| swatch(move || recent, move || color.set(recent)),
+    )
+    .style(|s| s.flex_row().gap(4., 0.));
+
+    v_stack((
+        plane,
+        hue_track,
+        alpha_track,
+        color_field::<DS>("Hex", color, HSLColor::to_hex, parse_hex),
+        color_field::<DS>("RGB", color, format_rgb, parse_rgb),
+        color_field::<DS>("HSL", color, format_hsl, parse_hsl),
+        theme_swatches,
+        recent_swatches,
+    ))
+    .style(|s| {
+        s.gap(0., DS::PADDING / 2.)
+            .padding(DS::PADDING)
+            .border(DS::BORDER_NORMAL)
+            .border_radius(DS::BORDER_RADIUS)
+            .ld_color(BorderColor, DS::BACKGROUND_3.base)
+            .ld_color(Background, DS::BACKGROUND.base)
+    })
+}
+
+/// A swatch of `color` that opens a [`color_picker`] below it. The picked color is added to
+/// `recents` when the picker closes.
+pub fn color_button<DS: DesignSystem + 'static>(
+    color: RwSignal<HSLColor>,
+    recents: RwSignal<im::Vector<HSLColor>>,
+) -> impl View {
+    let open = create_rw_signal(false);
+    create_effect(move |was_open: Option<bool>| {
+        let is_open = open.get();
+        if was_open == Some(true) && !is_open {
+            push_recent_color(recents, color.get_untracked());
+        }
+        is_open
+    });
+
+    let button = container(empty().style(move |s| {
+        s.size(SWATCH * 1.5, SWATCH * 1.5)
+            .border_radius(DS::BORDER_RADIUS)
+            .background(color.get().color())
+    }))
+    .keyboard_navigatable()
+    .on_click_stop(move |_| open.update(|open| *open = !*open))
+    .style(move |s| {
+        s.padding(2.)
+            .border(DS::BORDER_SMALL)
+            .border_radius(DS::BORDER_RADIUS)
+            .ld_color(BorderColor, DS::BACKGROUND_3.base)
+    });
+
+    pop_over(
+        button,
+        move || color_picker::<DS>(color, recents),
+        open,
+        Placement::new(Side::Bottom, Align::Start).offset(3.),
+    )
+    .on_click_outside(move || open.set(false))
+}
+
+/// Edits both sides of a [`LightDark`] with a [`color_button`] each.
+pub fn light_dark_picker<DS: DesignSystem + 'static>(
+    color: RwSignal<LightDark>,
+    recents: RwSignal<im::Vector<HSLColor>>,
+) -> impl View {
+    let side = |get: fn(&LightDark) -> HSLColor, set: fn(&mut LightDark, HSLColor)| {
+        let side = create_rw_signal(color.with_untracked(get));
+        create_effect(move |_| {
+            let current = color.with(get);
+            if side.get_untracked() != current {
+                side.set(current);
+            }
+        });
+        create_effect(move |_| {
+            let new = side.get();
+            if color.with_untracked(get) != new {
+                color.update(|color| set(color, new));
+            }
+        });
+        side
+    };
+    let light = side(|color| color.light, |color, new| color.light = new);
+    let dark = side(|color| color.dark, |color, new| color.dark = new);
+
+    let labelled = |name: &'static str, side: RwSignal<HSLColor>| {
+        h_stack((label(move || name), color_button::<DS>(side, recents)))
+            .style(|s| s.items_center().gap(DS::PADDING / 2., 0.))
+    };
+    h_stack((labelled("Light", light), labelled("Dark", dark)))
+        .style(|s| s.items_center().gap(DS::PADDING, 0.))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT: HSLColor = HSLColor::new(10, 20, 30, 40);
+
+    #[test]
+    fn rgb_text_round_trips() {
+        for text in ["255, 0, 0", "0, 255, 255", "128, 128, 128"] {
+            let color = parse_rgb(text, CURRENT).unwrap();
+            assert_eq!(format_rgb(color), text);
+            // the typed text has no alpha, so the current one is kept
+            assert_eq!(color.alpha(), 40);
+        }
+    }
+
+    #[test]
+    fn rgb_text_accepts_spaces_and_rejects_out_of_range() {
+        let red = Some(HSLColor::from_rgb(255, 0, 0, 40));
+        assert_eq!(parse_rgb("255 0 0", CURRENT), red);
+        assert_eq!(parse_rgb(" 255,0 ,0 ", CURRENT), red);
+        assert_eq!(parse_rgb("256, 0, 0", CURRENT), None);
+        assert_eq!(parse_rgb("-1, 0, 0", CURRENT), None);
+        assert_eq!(parse_rgb("255, 0", CURRENT), None);
+        assert_eq!(parse_rgb("255, 0, 0, 0", CURRENT), None);
+        assert_eq!(parse_rgb("red", CURRENT), None);
+    }
+
+    #[test]
+    fn hsl_text_round_trips() {
+        let color = HSLColor::new(50, 100, 50, 40);
+        assert_eq!(format_hsl(color), "180°, 100%, 50%");
+        assert_eq!(parse_hsl(&format_hsl(color), CURRENT), Some(color));
+        assert_eq!(parse_hsl("180 100 50", CURRENT), Some(color));
+    }
+
+    #[test]
+    fn hsl_text_wraps_hue_at_360() {
+        let full_turn = parse_hsl("360°, 100%, 50%", CURRENT).unwrap();
+        let zero = parse_hsl("0°, 100%, 50%", CURRENT).unwrap();
+        assert_eq!(full_turn.to_rgb(), zero.to_rgb());
+        assert_eq!(parse_hsl("361, 100, 50", CURRENT), None);
+        assert_eq!(parse_hsl("0, 101, 50", CURRENT), None);
+        assert_eq!(parse_hsl("0, 100, 101", CURRENT), None);
+    }
+
+    #[test]
+    fn hex_text_keeps_alpha_unless_given() {
+        assert_eq!(parse_hex("#f00", CURRENT).unwrap().alpha(), 40);
+        assert_eq!(parse_hex("#ff0000", CURRENT).unwrap().alpha(), 40);
+        assert_eq!(parse_hex("#ff000080", CURRENT).unwrap().alpha(), 50);
+        assert_eq!(parse_hex("#ff0000ff", CURRENT).unwrap().alpha(), 100);
+        assert_eq!(parse_hex("nope", CURRENT), None);
+    }
+}
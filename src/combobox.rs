@@ -0,0 +1,336 @@
+use std::rc::Rc;
+
+use floem::{
+    cosmic_text::Weight,
+    event::EventListener,
+    reactive::{create_effect, create_rw_signal, ReadSignal, RwSignal},
+    style::{Background, BorderColor, Display, TextColor},
+    view::View,
+    views::{container, label, list, text_input, v_stack, Decorators},
+    EventPropagation,
+};
+
+use crate::{
+    click_outside::ExtClickOutside,
+    dropdown::{dropdown_rows, DPStyles, DropDownStyles, ListControls},
+    fuzzy::{fuzzy_filter, fuzzy_match, highlight_segments, FuzzyMatch},
+    keymap::{KeyPress, Modifiers},
+    pop_over::{pop_over, Align, Placement, Side},
+    style::{DesignSystem, EventHandlers, ExtAnyEvent, ExtDynamicColor},
+};
+
+/// What the user committed in a [`combobox`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComboValue<T> {
+    Item(T),
+    /// Text that matched no option, only with [`ComboboxOptions::allow_free_text`]
+    Text(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ComboboxOptions {
+    /// Commit the typed text when Enter is pressed and nothing is highlighted. Only a result
+    /// whose label is exactly the typed text is highlighted on its own then, so Enter keeps
+    /// what was typed unless a result was picked with the arrow keys.
+    pub allow_free_text: bool,
+    /// Only list this many of the best matches. Only the rows in view are built, so all of them
+    /// are listed by default.
+    pub max_results: Option<usize>,
+}
+impl Default for ComboboxOptions {
+    fn default() -> Self {
+        Self {
+            allow_free_text: false,
+            max_results: None,
+        }
+    }
+}
+
+/// Shown in place of the options of a combobox whose options are loaded elsewhere.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListStatus {
+    Loading,
+    Error(String),
+}
+
+/// Where the options of a combobox come from.
+#[derive(Clone)]
+pub(crate) enum OptionSource {
+    /// All options are known up front and filtered by the typed text
+    Local,
+    /// The options were already picked for the typed text, e.g. by a backend query. They are
+    /// shown in their own order, with `status` above them while loading or after a failure.
+    Remote {
+        status: Rc<dyn Fn() -> Option<ListStatus>>,
+    },
+}
+
+/// A text input over a dropdown list. Typing filters `items` by fuzzy matching their labels,
+/// best matches first, with the matched characters highlighted. Up/Down/Home/End/PageUp/PageDown
+/// move through the results like in a dropdown, Enter commits and Escape restores the last
+/// committed text.
+pub fn combobox<DS, T>(
+    items: ReadSignal<im::Vector<T>>,
+    label_fn: impl Fn(&T) -> String + Copy + 'static,
+    on_select: impl Fn(ComboValue<T>) + Copy + 'static,
+    options: ComboboxOptions,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    T: Clone + 'static,
+{
+    combobox_with::<DS, T>(
+        items,
+        create_rw_signal(String::new()),
+        create_rw_signal(String::new()),
+        OptionSource::Local,
+        label_fn,
+        on_select,
+        options,
+        styles,
+    )
+}
+
+/// `committed` is the text of the last commit; the list opens once `query` differs from it. To
+/// show a value set from outside, set `committed` before `query`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn combobox_with<DS, T>(
+    items: ReadSignal<im::Vector<T>>,
+    query: RwSignal<String>,
+    committed: RwSignal<String>,
+    source: OptionSource,
+    label_fn: impl Fn(&T) -> String + Copy + 'static,
+    on_select: impl Fn(ComboValue<T>) + Copy + 'static,
+    options: ComboboxOptions,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    T: Clone + 'static,
+{
+    let results: RwSignal<im::Vector<(usize, FuzzyMatch)>> = create_rw_signal(im::Vector::new());
+    let nav = ListControls::new(
+        results.read_only(),
+        |(item, _)| Some(*item),
+        styles.with_untracked(|val| val.row_height),
+    );
+    let ListControls {
+        open, highlighted, ..
+    } = nav;
+    let (box_func, box_size) = crate::style::lazy_size();
+    let remote = matches!(source, OptionSource::Remote { .. });
+
+    create_effect(move |_| {
+        let pattern = query.get();
+        let mut found: im::Vector<(usize, FuzzyMatch)> = items.with(|list| {
+            if !remote {
+                return fuzzy_filter(list.iter(), &pattern, |item| label_fn(item))
+                    .into_iter()
+                    .collect();
+            }
+            // keep the given order, only look for characters to highlight
+            list.iter()
+                .enumerate()
+                .map(|(idx, item)| {
+                    let found = fuzzy_match(&pattern, &label_fn(item)).unwrap_or(FuzzyMatch {
+                        score: 0,
+                        indices: Vec::new(),
+                    });
+                    (idx, found)
+                })
+                .collect()
+        });
+        if let Some(max) = options.max_results {
+            found.truncate(max.min(found.len()));
+        }
+        let typed = committed.with_untracked(|committed| committed != &pattern);
+        let best = if found.is_empty() || (pattern.is_empty() && !typed) {
+            None
+        } else if options.allow_free_text {
+            let wanted = pattern.trim().to_lowercase();
+            items.with_untracked(|list| {
+                found
+                    .iter()
+                    .position(|(item, _)| label_fn(&list[*item]).to_lowercase() == wanted)
+            })
+        } else {
+            Some(0)
+        };
+        results.set(found);
+        highlighted.set(best);
+        if typed {
+            open.set(true);
+        }
+    });
+
+    let commit = move |value: ComboValue<T>| {
+        let text = match &value {
+            ComboValue::Item(item) => label_fn(item),
+            ComboValue::Text(text) => text.clone(),
+        };
+        committed.set(text.clone());
+        query.set(text);
+        open.set(false);
+        on_select(value);
+    };
+    let commit_result = move |idx: usize| {
+        let item = results
+            .with_untracked(|results| results.get(idx).map(|(item, _)| *item))
+            .and_then(|item| items.with_untracked(|list| list.get(item).cloned()));
+        if let Some(item) = item {
+            commit(ComboValue::Item(item));
+        }
+    };
+    let restore = move || {
+        open.set(false);
+        query.set(committed.get_untracked());
+    };
+
+    let key_press = move |press: &KeyPress| {
+        // keep Shift+Home and the like for selecting text
+        if press.mods != Modifiers::default() {
+            return false;
+        }
+        let is_open = open.get_untracked();
+        let len = results.with_untracked(|results| results.len());
+        match press.key.as_str() {
+            "down" | "up" => {
+                open.set(true);
+                nav.navigate(&press.key, len, |_| true);
+            }
+            "home" | "end" | "pagedown" | "pageup" if is_open => {
+                nav.navigate(&press.key, len, |_| true);
+            }
+            "enter" => match highlighted.get_untracked() {
+                Some(idx) if is_open => commit_result(idx),
+                _ if options.allow_free_text => commit(ComboValue::Text(query.get_untracked())),
+                _ => return false,
+            },
+            "esc" if is_open => restore(),
+            _ => return false,
+        }
+        true
+    };
+
+    let input = text_input(query)
+        .keyboard_navigatable()
+        .event_handlers(EventHandlers::new().on_key_press(key_press))
+        .on_resize(box_func)
+        .style(move |s| {
+            let styles = styles.get();
+            s.width_full()
+                .padding_horiz(DPStyles::SCROLL_CONTAINER_PADDING)
+                .border(DS::BORDER_SMALL)
+                .border_radius(DS::BORDER_RADIUS)
+                .ld_color(BorderColor, styles.main_box_border)
+                .ld_color(Background, styles.main_box_back)
+        });
+
+    let status = match &source {
+        OptionSource::Local => None,
+        OptionSource::Remote { status } => Some(status.clone()),
+    };
+    let status = move || status.as_ref().and_then(|status| status());
+
+    let drop_box = move || {
+        let status = status.clone();
+        let status_row = label({
+            let status = status.clone();
+            move || match status() {
+                Some(ListStatus::Loading) => "Loading…".to_string(),
+                Some(ListStatus::Error(error)) => error,
+                None => String::new(),
+            }
+        })
+        .style({
+            let status = status.clone();
+            move |s| {
+                let status = status();
+                s.width_full()
+                    .padding_horiz(DPStyles::SCROLL_CONTAINER_PADDING)
+                    .ld_color(TextColor, DS::TEXT.base.set_alpha(60))
+                    .apply_if(matches!(status, Some(ListStatus::Error(_))), |s| {
+                        s.ld_color(TextColor, DS::DANGER.base)
+                    })
+                    .apply_if(status.is_none(), |s| s.display(Display::None))
+            }
+        });
+        let rows = dropdown_rows(
+            results.read_only(),
+            // the matched characters change with the query, so rows are rebuilt with them
+            |(item, found)| (*item, found.indices.clone()),
+            nav,
+            move |pos, (item, found)| {
+                let text = items
+                    .with_untracked(|list| list.get(item).map(label_fn))
+                    .unwrap_or_default();
+                container(highlighted_label(&text, &found.indices, styles))
+                    .on_click_stop(move |_| commit_result(pos))
+                    .on_event(EventListener::PointerEnter, move |_| {
+                        highlighted.set(Some(pos));
+                        EventPropagation::Continue
+                    })
+                    .style(move |s| {
+                        let sel_cont = styles.with(|val| val.sel_cont.clone());
+                        s.width_full()
+                            .padding_horiz(DPStyles::SCROLL_CONTAINER_PADDING)
+                            .apply_if(highlighted.get() == Some(pos), |s| {
+                                s.ld_color(Background, sel_cont.hover)
+                            })
+                    })
+            },
+            |s, styles| {
+                s.apply_opt(styles.scroll_height, |s, val| s.max_height(val))
+                    .apply_opt(styles.max_scroll_height, |s, val| s.max_height(val))
+            },
+            styles,
+        );
+
+        v_stack((status_row, rows)).style(move |s| {
+            let styles = styles.get();
+            let empty = results.with(|results| results.is_empty()) && status().is_none();
+            s.min_width(box_size().width)
+                .border(DS::BORDER_NORMAL)
+                .border_radius(DS::BORDER_RADIUS)
+                .ld_color(BorderColor, styles.drop_box_border)
+                .ld_color(Background, styles.drop_box_back)
+                .apply_if(empty, |s| s.display(Display::None))
+        })
+    };
+
+    pop_over(
+        input,
+        drop_box,
+        open,
+        Placement::new(Side::Bottom, Align::Start).offset(3.),
+    )
+    .on_click_outside(move || {
+        if open.get_untracked() {
+            restore();
+        }
+    })
+    .style(|s| s.width_full())
+}
+
+/// A label with the characters at `indices` emphasized.
+pub fn highlighted_label(
+    text: &str,
+    indices: &[usize],
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View {
+    let segments = highlight_segments(text, indices);
+    list(
+        move || segments.clone().into_iter().enumerate(),
+        |(idx, _)| *idx,
+        move |(_, (segment, matched))| {
+            label(move || segment.clone()).style(move |s| {
+                s.apply_if(matched, |s| {
+                    s.font_weight(Weight::BOLD)
+                        .ld_color(TextColor, styles.with(|val| val.sel_cont.base))
+                })
+            })
+        },
+    )
+    .style(|s| s.flex_row())
+}
@@ -1 +1,443 @@
+use std::{rc::Rc, time::Duration};
 
+use floem::{
+    event::{Event, EventListener},
+    keyboard::{Key, NamedKey},
+    reactive::{create_effect, create_rw_signal, ReadSignal, RwSignal},
+    style::{
+        Background, BorderColor, CursorStyle, Display, InsetLeft, Position, TextColor, Transition,
+    },
+    taffy::style::FlexDirection,
+    view::View,
+    views::{container, empty, h_stack, label, list, Decorators, Svg},
+    EventPropagation,
+};
+
+use crate::{
+    gesture::TimerSlot,
+    icons::icon_circle,
+    style::{DesignSystem, ExtDynamicColor, HSLColor, LightDark, ResponsiveColor},
+};
+
+const SPINNER_FRAME: Duration = Duration::from_millis(100);
+const SPINNER_DOTS: usize = 8;
+const SWITCH_ANIMATION: Duration = Duration::from_millis(150);
+const SWITCH_INSET: f32 = 2.;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ButtonVariant {
+    #[default]
+    Primary,
+    Secondary,
+    /// No background until hovered
+    Ghost,
+    Danger,
+    /// Looks like a text link
+    Link,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ButtonSize {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+impl ButtonSize {
+    fn scale(self) -> f32 {
+        match self {
+            Self::Small => 0.85,
+            Self::Medium => 1.,
+            Self::Large => 1.25,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ButtonOptions {
+    pub variant: ButtonVariant,
+    pub size: ButtonSize,
+    /// Icons from [`crate::icons`], e.g. `icon_chevron_down`
+    pub leading: Option<fn() -> Svg>,
+    pub trailing: Option<fn() -> Svg>,
+    pub disabled: Option<ReadSignal<bool>>,
+    /// Shows a busy indicator in place of the content and ignores clicks while true
+    pub loading: Option<ReadSignal<bool>>,
+}
+impl ButtonOptions {
+    pub fn variant(mut self, variant: ButtonVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn size(mut self, size: ButtonSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn leading(mut self, icon: fn() -> Svg) -> Self {
+        self.leading = Some(icon);
+        self
+    }
+
+    pub fn trailing(mut self, icon: fn() -> Svg) -> Self {
+        self.trailing = Some(icon);
+        self
+    }
+
+    pub fn disabled(mut self, disabled: ReadSignal<bool>) -> Self {
+        self.disabled = Some(disabled);
+        self
+    }
+
+    pub fn loading(mut self, loading: ReadSignal<bool>) -> Self {
+        self.loading = Some(loading);
+        self
+    }
+
+    fn is_disabled(&self) -> bool {
+        self.disabled.is_some_and(|disabled| disabled.get())
+    }
+
+    fn is_loading(&self) -> bool {
+        self.loading.is_some_and(|loading| loading.get())
+    }
+}
+
+/// Background and text colors of a variant.
+fn variant_colors<DS: DesignSystem>(variant: ButtonVariant) -> (ResponsiveColor, ResponsiveColor) {
+    let fixed = |color: LightDark| {
+        ResponsiveColor::from_lightdark(color)
+            .set_hover_to_base()
+            .reduce_active_to_hover()
+    };
+    match variant {
+        ButtonVariant::Primary => (
+            ResponsiveColor {
+                base: DS::ACCENT.base,
+                ..DS::HOVER_ACCENT
+            },
+            fixed(DS::BACKGROUND.base),
+        ),
+        ButtonVariant::Secondary => (DS::HOVER_ACCENT_3, fixed(DS::TEXT.base)),
+        ButtonVariant::Ghost => (DS::HOVER_ACCENT.disable_base(), fixed(DS::TEXT.base)),
+        ButtonVariant::Danger => (DS::DANGER, fixed(DS::BACKGROUND.base)),
+        ButtonVariant::Link => (
+            ResponsiveColor::from_lightdark(LightDark::transparent()),
+            DS::ACCENT,
+        ),
+    }
+}
+
+fn icon_view<DS: DesignSystem + 'static>(
+    icon: Option<fn() -> Svg>,
+    size: ButtonSize,
+) -> Box<dyn View> {
+    let icon_size = DS::BASE_FONT_SIZE * size.scale();
+    match icon {
+        Some(icon) => Box::new(icon().style(move |s| s.size(icon_size, icon_size))),
+        None => Box::new(empty().style(|s| s.display(Display::None))),
+    }
+}
+
+fn start_spinner_frames(frame: RwSignal<usize>, timer: TimerSlot) {
+    let next = timer.clone();
+    timer.start(SPINNER_FRAME, move || {
+        frame.update(|frame| *frame = (*frame + 1) % SPINNER_DOTS);
+        start_spinner_frames(frame, next);
+    });
+}
+
+/// A ring of dots, `size` across, that spins while `active` is true.
+pub fn loading_spinner(
+    active: impl Fn() -> bool + 'static,
+    color: LightDark,
+    size: f32,
+) -> impl View {
+    let frame = create_rw_signal(0);
+    let timer = TimerSlot::default();
+    create_effect({
+        let timer = timer.clone();
+        move |_| {
+            if active() {
+                start_spinner_frames(frame, timer.clone());
+            } else {
+                timer.cancel();
+            }
+        }
+    });
+
+    let dot_size = size / 4.;
+    let radius = (size - dot_size) / 2.;
+    list(
+        || 0..SPINNER_DOTS,
+        |idx| *idx,
+        move |idx| {
+            let angle = idx as f32 / SPINNER_DOTS as f32 * std::f32::consts::TAU;
+            let (left, top) = (radius + radius * angle.sin(), radius - radius * angle.cos());
+            empty().style(move |s| {
+                // the lit dot leads and the ones behind it fade out
+                let behind = (frame.get() + SPINNER_DOTS - idx) % SPINNER_DOTS;
+                let alpha = 100u8.saturating_sub(behind as u8 * 12).max(25);
+                s.position(Position::Absolute)
+                    .inset_left(left)
+                    .inset_top(top)
+                    .size(dot_size, dot_size)
+                    .border_radius(dot_size / 2.)
+                    .ld_color(Background, color.set_alpha(alpha))
+            })
+        },
+    )
+    .on_cleanup(move || timer.cancel())
+    .style(move |s| s.size(size, size))
+}
+
+/// The frame shared by all buttons. `pressed` keeps the active color, for toggle buttons.
+fn button_frame<DS: DesignSystem + 'static>(
+    content: impl View + 'static,
+    options: ButtonOptions,
+    square: bool,
+    pressed: impl Fn() -> bool + 'static,
+    on_click: impl Fn() + 'static,
+) -> impl View {
+    let (background, text) = variant_colors::<DS>(options.variant);
+    let scale = options.size.scale();
+    let padding = DS::PADDING * scale;
+    let font_size = DS::BASE_FONT_SIZE * scale;
+    let busy = move || options.is_disabled() || options.is_loading();
+    let on_click = Rc::new(on_click);
+    let click = move || {
+        if !busy() {
+            on_click();
+        }
+    };
+    let click = Rc::new(click);
+    let click_key = click.clone();
+
+    let spinner = loading_spinner(move || options.is_loading(), text.base, font_size)
+        .style(move |s| s.apply_if(!options.is_loading(), |s| s.display(Display::None)));
+    let content = container(content)
+        .style(move |s| s.apply_if(options.is_loading(), |s| s.display(Display::None)));
+
+    h_stack((content, spinner))
+        .keyboard_navigatable()
+        // disabled views are skipped by Tab and don't take focus
+        .disabled(move || options.is_disabled())
+        .on_click_stop(move |_| click())
+        .on_event(EventListener::KeyDown, move |event| {
+            let Event::KeyDown(key_event) = event else {
+                return EventPropagation::Continue;
+            };
+            match &key_event.key.logical_key {
+                Key::Named(NamedKey::Enter | NamedKey::Space) => {
+                    click_key();
+                    EventPropagation::Stop
+                }
+                _ => EventPropagation::Continue,
+            }
+        })
+        .style(move |s| {
+            let disabled = options.is_disabled();
+            let (background, text) = if disabled {
+                (
+                    background
+                        .clone()
+                        .set_hover_to_base()
+                        .reduce_active_to_hover()
+                        .set_alpha(50),
+                    text.clone()
+                        .set_hover_to_base()
+                        .reduce_active_to_hover()
+                        .set_alpha(50),
+                )
+            } else {
+                (background.clone(), text.clone())
+            };
+            s.items_center()
+                .justify_center()
+                .font_size(font_size)
+                .border_radius(DS::BORDER_RADIUS)
+                // the ring is always there so focusing doesn't change the size
+                .border(DS::BORDER_NORMAL)
+                .ld_color(BorderColor, LightDark::transparent())
+                .padding_vert(padding / 2.)
+                .padding_horiz(if square { padding / 2. } else { padding })
+                .dynamic_color(Background, background)
+                .dynamic_color(TextColor, text)
+                .apply_if(pressed(), |s| {
+                    s.ld_color(Background, DS::HOVER_ACCENT.active)
+                })
+                .apply_if(!busy(), |s| s.cursor(CursorStyle::Pointer))
+                .apply_if(!disabled, |s| {
+                    s.focus_visible(|s| s.ld_color(BorderColor, DS::TEXT.base))
+                })
+        })
+}
+
+fn text_content<DS: DesignSystem + 'static>(
+    text: impl Fn() -> String + 'static,
+    options: ButtonOptions,
+) -> impl View {
+    h_stack((
+        icon_view::<DS>(options.leading, options.size),
+        label(text),
+        icon_view::<DS>(options.trailing, options.size),
+    ))
+    .style(|s| s.items_center().gap(DS::PADDING / 2., 0.))
+}
+
+/// A text button with optional icons around the text.
+pub fn button<DS: DesignSystem + 'static>(
+    text: impl Fn() -> String + 'static,
+    options: ButtonOptions,
+    on_click: impl Fn() + 'static,
+) -> impl View {
+    let content = text_content::<DS>(text, options);
+    button_frame::<DS>(content, options, false, || false, on_click)
+}
+
+/// A square button showing only `icon`.
+pub fn icon_button<DS: DesignSystem + 'static>(
+    icon: fn() -> Svg,
+    options: ButtonOptions,
+    on_click: impl Fn() + 'static,
+) -> impl View {
+    let content = icon_view::<DS>(Some(icon), options.size);
+    button_frame::<DS>(content, options, true, || false, on_click)
+}
+
+/// A [`button`] that flips `pressed` when clicked and stays highlighted while it's set.
+pub fn toggle_button<DS: DesignSystem + 'static>(
+    text: impl Fn() -> String + 'static,
+    pressed: RwSignal<bool>,
+    options: ButtonOptions,
+) -> impl View {
+    let content = text_content::<DS>(text, options);
+    button_frame::<DS>(
+        content,
+        options,
+        false,
+        move || pressed.get(),
+        move || pressed.update(|pressed| *pressed = !*pressed),
+    )
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LabelPosition {
+    Leading,
+    #[default]
+    Trailing,
+}
+
+#[derive(Clone, Default)]
+pub struct SwitchOptions {
+    pub label: Option<String>,
+    pub label_position: LabelPosition,
+    pub disabled: Option<ReadSignal<bool>>,
+}
+impl SwitchOptions {
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn label_position(mut self, position: LabelPosition) -> Self {
+        self.label_position = position;
+        self
+    }
+
+    pub fn disabled(mut self, disabled: ReadSignal<bool>) -> Self {
+        self.disabled = Some(disabled);
+        self
+    }
+}
+
+/// An on/off switch bound to `on`. The knob slides across when it flips; clicking the switch
+/// or its label, Space and Enter all toggle it.
+pub fn toggle_switch<DS: DesignSystem + 'static>(
+    on: RwSignal<bool>,
+    options: SwitchOptions,
+) -> impl View {
+    let height = DS::BASE_FONT_SIZE * 1.3;
+    let width = height * 1.8;
+    let knob_size = height - SWITCH_INSET * 2.;
+    // light in both modes so it stands out on either track
+    let knob_color = LightDark::new(HSLColor::new(0, 0, 98, 100), HSLColor::new(0, 0, 92, 100));
+
+    let disabled = options.disabled;
+    let is_disabled = move || disabled.is_some_and(|disabled| disabled.get());
+    let toggle = move || {
+        if !is_disabled() {
+            on.update(|on| *on = !*on);
+        }
+    };
+
+    let knob = icon_circle().style(move |s| {
+        s.position(Position::Absolute)
+            .inset_top(SWITCH_INSET)
+            .inset_left(if on.get() {
+                width - knob_size - SWITCH_INSET
+            } else {
+                SWITCH_INSET
+            })
+            .size(knob_size, knob_size)
+            .ld_color(
+                TextColor,
+                knob_color.set_alpha(if is_disabled() { 60 } else { 100 }),
+            )
+            .transition(
+                InsetLeft,
+                Transition::linear(SWITCH_ANIMATION.as_secs_f64()),
+            )
+    });
+    let track = container(knob).style(move |s| {
+        let alpha = if is_disabled() { 50 } else { 100 };
+        let color = if on.get() {
+            DS::ACCENT
+        } else {
+            DS::BACKGROUND_3
+        };
+        s.size(width, height)
+            .border_radius(height / 2.)
+            .dynamic_color(Background, color.set_alpha(alpha))
+    });
+
+    let text = options.label.clone().unwrap_or_default();
+    let has_label = options.label.is_some();
+    let text = label(move || text.clone()).style(move |s| {
+        s.apply_if(!has_label, |s| s.display(Display::None))
+            .apply_if(is_disabled(), |s| {
+                s.ld_color(TextColor, DS::TEXT.base.set_alpha(50))
+            })
+    });
+    let leading = options.label_position == LabelPosition::Leading;
+
+    h_stack((track, text))
+        .keyboard_navigatable()
+        .disabled(move || is_disabled())
+        .on_click_stop(move |_| toggle())
+        .on_event(EventListener::KeyDown, move |event| {
+            let Event::KeyDown(key_event) = event else {
+                return EventPropagation::Continue;
+            };
+            match &key_event.key.logical_key {
+                Key::Named(NamedKey::Enter | NamedKey::Space) => {
+                    toggle();
+                    EventPropagation::Stop
+                }
+                _ => EventPropagation::Continue,
+            }
+        })
+        .style(move |s| {
+            s.items_center()
+                .gap(DS::PADDING / 2., 0.)
+                .apply_if(leading, |s| s.flex_direction(FlexDirection::RowReverse))
+                .border(DS::BORDER_NORMAL)
+                .border_radius(DS::BORDER_RADIUS)
+                .ld_color(BorderColor, LightDark::transparent())
+                .apply_if(!is_disabled(), |s| {
+                    s.cursor(CursorStyle::Pointer)
+                        .focus_visible(|s| s.ld_color(BorderColor, DS::TEXT.base))
+                })
+        })
+}
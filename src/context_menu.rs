@@ -0,0 +1,454 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use floem::{
+    action::exec_after,
+    event::{Event, EventListener},
+    keyboard::{Key, NamedKey},
+    kurbo::{Point, Rect, Size},
+    pointer::PointerButton,
+    reactive::{create_rw_signal, RwSignal},
+    style::{Background, TextColor},
+    view::View,
+    views::{container, empty, h_stack, label, list, v_stack, Decorators, Svg},
+    EventPropagation,
+};
+
+use crate::{
+    click_outside::ExtClickOutside,
+    icons::icon_circle_check_solid,
+    list_nav::{next_enabled, TypeAhead},
+    pop_over::{pop_over_rect, Align, Placement, Side},
+    popover::{
+        close_popover, close_top_popover, is_top_popover, managed_pop_over, register_popover,
+        unregister_popover, PopoverKey,
+    },
+    style::{DesignSystem, ExtDynamicColor, LightDark},
+};
+
+#[derive(Clone)]
+pub enum MenuItem {
+    Action {
+        label: String,
+        icon: Option<fn() -> Svg>,
+        shortcut: Option<String>,
+        disabled: bool,
+        /// Checkable items show a check mark while the signal is true and toggle it when chosen
+        checked: Option<RwSignal<bool>>,
+        action: Rc<dyn Fn()>,
+    },
+    Submenu {
+        label: String,
+        icon: Option<fn() -> Svg>,
+        disabled: bool,
+        items: Rc<Vec<MenuItem>>,
+    },
+    Separator,
+}
+impl MenuItem {
+    pub fn action(label: impl Into<String>, action: impl Fn() + 'static) -> Self {
+        Self::Action {
+            label: label.into(),
+            icon: None,
+            shortcut: None,
+            disabled: false,
+            checked: None,
+            action: Rc::new(action),
+        }
+    }
+
+    pub fn checkable(label: impl Into<String>, checked: RwSignal<bool>) -> Self {
+        Self::Action {
+            label: label.into(),
+            icon: None,
+            shortcut: None,
+            disabled: false,
+            checked: Some(checked),
+            action: Rc::new(|| {}),
+        }
+    }
+
+    pub fn submenu(label: impl Into<String>, items: Vec<MenuItem>) -> Self {
+        Self::Submenu {
+            label: label.into(),
+            icon: None,
+            disabled: false,
+            items: Rc::new(items),
+        }
+    }
+
+    pub fn separator() -> Self {
+        Self::Separator
+    }
+
+    /// An icon from [`crate::icons`], e.g. `icon_palette`.
+    pub fn icon(mut self, new_icon: fn() -> Svg) -> Self {
+        match &mut self {
+            Self::Action { icon, .. } | Self::Submenu { icon, .. } => *icon = Some(new_icon),
+            Self::Separator => {}
+        }
+        self
+    }
+
+    /// Shortcut text shown on the right, e.g. `"Ctrl+C"`.
+    pub fn shortcut(mut self, text: impl Into<String>) -> Self {
+        if let Self::Action { shortcut, .. } = &mut self {
+            *shortcut = Some(text.into());
+        }
+        self
+    }
+
+    pub fn disabled(mut self, value: bool) -> Self {
+        match &mut self {
+            Self::Action { disabled, .. } | Self::Submenu { disabled, .. } => *disabled = value,
+            Self::Separator => {}
+        }
+        self
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Self::Action { label, .. } | Self::Submenu { label, .. } => label.clone(),
+            Self::Separator => String::new(),
+        }
+    }
+
+    fn selectable(&self) -> bool {
+        match self {
+            Self::Action { disabled, .. } | Self::Submenu { disabled, .. } => !disabled,
+            Self::Separator => false,
+        }
+    }
+}
+
+pub trait ExtContextMenu: Sized {
+    /// Open a menu at the pointer on secondary click. `items` is called every time the menu
+    /// opens, so it can reflect the current state.
+    fn context_menu<DS: DesignSystem + 'static>(
+        self,
+        items: impl Fn() -> Vec<MenuItem> + 'static,
+    ) -> Self;
+}
+impl<T: View + Decorators> ExtContextMenu for T {
+    fn context_menu<DS: DesignSystem + 'static>(
+        self,
+        items: impl Fn() -> Vec<MenuItem> + 'static,
+    ) -> Self {
+        let owner = self.id();
+        let visible = create_rw_signal(false);
+        let anchor = create_rw_signal(Rect::ZERO);
+        let window_origin = create_rw_signal(Point::ZERO);
+        let current: Rc<RefCell<Rc<Vec<MenuItem>>>> = Rc::new(RefCell::new(Rc::new(Vec::new())));
+        let key = register_popover(visible, None, Some(owner));
+
+        let menu_items = current.clone();
+        let state = pop_over_rect(
+            anchor,
+            move || {
+                menu::<DS>(menu_items.borrow().clone(), key, key, false).on_click_outside(
+                    move || {
+                        if is_top_popover(key) {
+                            visible.set(false);
+                        }
+                    },
+                )
+            },
+            visible,
+            Placement::new(Side::Bottom, Align::Start).offset(0.),
+            owner,
+        );
+
+        self.on_move(move |point| window_origin.set(point))
+            .on_event(EventListener::PointerDown, move |event| {
+                let Event::PointerDown(pointer) = event else {
+                    return EventPropagation::Continue;
+                };
+                if !matches!(pointer.button, PointerButton::Secondary) {
+                    return EventPropagation::Continue;
+                }
+                // reopen at the new position with fresh items
+                visible.set(false);
+                *current.borrow_mut() = Rc::new(items());
+                let at = window_origin.get_untracked() + pointer.pos.to_vec2();
+                anchor.set(Rect::from_origin_size(at, Size::ZERO));
+                visible.set(true);
+                EventPropagation::Stop
+            })
+            .on_cleanup(move || {
+                state.remove();
+                unregister_popover(key);
+            })
+    }
+}
+
+/// A menu list. `this` is the popover the list lives in and `root` the menu at the bottom of the
+/// stack, which is closed once an item is chosen.
+fn menu<DS: DesignSystem + 'static>(
+    items: Rc<Vec<MenuItem>>,
+    this: PopoverKey,
+    root: PopoverKey,
+    highlight_first: bool,
+) -> impl View {
+    let len = items.len();
+    let enabled = {
+        let items = items.clone();
+        move |idx: usize| items[idx].selectable()
+    };
+    let highlighted = create_rw_signal(
+        highlight_first
+            .then(|| next_enabled(None, len, 1, true, &enabled))
+            .flatten(),
+    );
+    // one visibility signal per row so opening a submenu can close its siblings
+    let submenus: Rc<Vec<RwSignal<bool>>> =
+        Rc::new((0..len).map(|_| create_rw_signal(false)).collect());
+    let opened_by_key = Rc::new(Cell::new(false));
+    let type_ahead = Rc::new(RefCell::new(TypeAhead::default()));
+
+    let open_submenu = {
+        let submenus = submenus.clone();
+        move |idx: usize| {
+            for (row, visible) in submenus.iter().enumerate() {
+                if row != idx && visible.get_untracked() {
+                    visible.set(false);
+                }
+            }
+            if !submenus[idx].get_untracked() {
+                submenus[idx].set(true);
+            }
+        }
+    };
+
+    let activate = {
+        let items = items.clone();
+        let open_submenu = open_submenu.clone();
+        let opened_by_key = opened_by_key.clone();
+        move |idx: usize, by_key: bool| match &items[idx] {
+            MenuItem::Action {
+                disabled: false,
+                checked,
+                action,
+                ..
+            } => {
+                if let Some(checked) = checked {
+                    checked.update(|val| *val = !*val);
+                }
+                action();
+                close_popover(root);
+            }
+            MenuItem::Submenu {
+                disabled: false, ..
+            } => {
+                opened_by_key.set(by_key);
+                open_submenu(idx);
+            }
+            _ => {}
+        }
+    };
+
+    let close_submenus = {
+        let submenus = submenus.clone();
+        move || {
+            for visible in submenus.iter().filter(|visible| visible.get_untracked()) {
+                visible.set(false);
+            }
+        }
+    };
+
+    let row_items = items.clone();
+    let row_submenus = submenus.clone();
+    let row_activate = activate.clone();
+    let row_open = open_submenu.clone();
+    let row_opened_by_key = opened_by_key.clone();
+    let rows = list(
+        move || 0..len,
+        |idx| *idx,
+        move |idx| -> Box<dyn View> {
+            let item = row_items[idx].clone();
+            let activate = row_activate.clone();
+            let open_submenu = row_open.clone();
+            let close_submenus = close_submenus.clone();
+            match item {
+                MenuItem::Separator => Box::new(
+                    container(empty().style(|s| {
+                        s.height(DS::BORDER_SMALL)
+                            .width_full()
+                            .ld_color(Background, DS::BACKGROUND_3.base)
+                    }))
+                    .style(|s| s.padding_vert(DS::PADDING / 2.)),
+                ),
+                MenuItem::Action {
+                    label: text,
+                    icon,
+                    shortcut,
+                    disabled,
+                    checked,
+                    ..
+                } => Box::new(
+                    menu_row::<DS>(
+                        text,
+                        icon,
+                        checked,
+                        shortcut.unwrap_or_default(),
+                        disabled,
+                        idx,
+                        highlighted,
+                    )
+                    .on_event(EventListener::PointerEnter, move |_| {
+                        close_submenus();
+                        highlighted.set(Some(idx));
+                        EventPropagation::Continue
+                    })
+                    .on_click_stop(move |_| activate(idx, false)),
+                ),
+                MenuItem::Submenu {
+                    label: text,
+                    icon,
+                    disabled,
+                    items: sub_items,
+                } => {
+                    let visible = row_submenus[idx];
+                    let opened_by_key = row_opened_by_key.clone();
+                    let sub_key: Rc<Cell<Option<PopoverKey>>> = Rc::new(Cell::new(None));
+                    let content_key = sub_key.clone();
+                    let row = menu_row::<DS>(
+                        text,
+                        icon,
+                        None,
+                        "›".to_string(),
+                        disabled,
+                        idx,
+                        highlighted,
+                    )
+                    .on_event(EventListener::PointerEnter, move |_| {
+                        highlighted.set(Some(idx));
+                        if !disabled {
+                            open_submenu(idx);
+                        }
+                        EventPropagation::Continue
+                    })
+                    .on_click_stop(move |_| activate(idx, false));
+                    let (view, key) = managed_pop_over(
+                        row,
+                        move || {
+                            let this = content_key.get().expect("submenu key is set on creation");
+                            menu::<DS>(sub_items.clone(), this, root, opened_by_key.get())
+                        },
+                        visible,
+                        Placement::new(Side::Right, Align::Start).offset(0.),
+                        Some(this),
+                    );
+                    sub_key.set(Some(key));
+                    Box::new(view)
+                }
+            }
+        },
+    );
+
+    let key_items = items.clone();
+    let key_enabled = enabled.clone();
+    let view = v_stack((rows,))
+        .keyboard_navigatable()
+        .on_event(EventListener::KeyDown, move |event| {
+            let Event::KeyDown(key_event) = event else {
+                return EventPropagation::Continue;
+            };
+            let current = highlighted.get_untracked();
+            let step = |step: isize| next_enabled(current, len, step, true, &key_enabled);
+            match &key_event.key.logical_key {
+                Key::Named(NamedKey::ArrowDown) => highlighted.set(step(1)),
+                Key::Named(NamedKey::ArrowUp) => highlighted.set(step(-1)),
+                Key::Named(NamedKey::Home) => {
+                    highlighted.set(next_enabled(None, len, 1, false, &key_enabled))
+                }
+                Key::Named(NamedKey::End) => {
+                    highlighted.set(next_enabled(None, len, -1, false, &key_enabled))
+                }
+                Key::Named(NamedKey::Enter | NamedKey::Space) => match current {
+                    Some(idx) => activate(idx, true),
+                    None => return EventPropagation::Continue,
+                },
+                Key::Named(NamedKey::ArrowRight) => match current {
+                    Some(idx) if matches!(key_items[idx], MenuItem::Submenu { .. }) => {
+                        activate(idx, true)
+                    }
+                    _ => return EventPropagation::Continue,
+                },
+                Key::Named(NamedKey::ArrowLeft) if this != root => close_popover(this),
+                Key::Named(NamedKey::Escape) if this == root => {
+                    close_top_popover();
+                }
+                // leave shortcuts such as Ctrl+C to whoever handles them
+                Key::Character(text)
+                    if !(key_event.modifiers.control_key()
+                        || key_event.modifiers.alt_key()
+                        || key_event.modifiers.super_key()) =>
+                {
+                    let mut type_ahead = type_ahead.borrow_mut();
+                    type_ahead.push(text, Instant::now());
+                    let found =
+                        type_ahead.find(current, len, |idx| key_items[idx].label(), &key_enabled);
+                    if found.is_some() {
+                        highlighted.set(found);
+                    }
+                }
+                _ => return EventPropagation::Continue,
+            }
+            EventPropagation::Stop
+        })
+        .style(|s| {
+            s.flex_col()
+                .min_width(160.)
+                .padding(DS::PADDING / 2.)
+                .border(DS::BORDER_SMALL)
+                .border_radius(DS::BORDER_RADIUS)
+                .ld_color(Background, DS::BACKGROUND_EXT.base)
+                .ld_color(TextColor, DS::TEXT.base)
+        });
+    // take focus once in the window so the arrow keys, type-ahead and Escape reach this menu
+    let id = view.id();
+    exec_after(Duration::ZERO, move |_| id.request_focus());
+    view
+}
+
+fn menu_row<DS: DesignSystem + 'static>(
+    text: String,
+    icon: Option<fn() -> Svg>,
+    checked: Option<RwSignal<bool>>,
+    trailing: String,
+    disabled: bool,
+    idx: usize,
+    highlighted: RwSignal<Option<usize>>,
+) -> impl View {
+    let icon_size = DS::BASE_FONT_SIZE;
+    let leading: Box<dyn View> = match (checked, icon) {
+        (Some(checked), _) => Box::new(icon_circle_check_solid().style(move |s| {
+            s.size(icon_size, icon_size).apply_if(!checked.get(), |s| {
+                s.color(LightDark::transparent().color())
+            })
+        })),
+        (None, Some(icon)) => Box::new(icon().style(move |s| s.size(icon_size, icon_size))),
+        (None, None) => Box::new(empty().style(move |s| s.size(icon_size, icon_size))),
+    };
+    h_stack((
+        leading,
+        label(move || text.clone()).style(|s| s.flex_grow(1.)),
+        label(move || trailing.clone()).style(|s| s.margin_left(DS::PADDING * 2.)),
+    ))
+    .style(move |s| {
+        s.items_center()
+            .gap(DS::PADDING, 0.)
+            .padding_horiz(DS::PADDING)
+            .padding_vert(DS::PADDING / 2.)
+            .border_radius(DS::BORDER_RADIUS / 2.)
+            .apply_if(highlighted.get() == Some(idx) && !disabled, |s| {
+                s.ld_color(Background, DS::ACCENT.base)
+            })
+            .apply_if(disabled, |s| {
+                s.ld_color(TextColor, DS::TEXT.base.set_alpha(40))
+            })
+    })
+}
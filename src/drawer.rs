@@ -0,0 +1,355 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use floem::{
+    action::{add_overlay, exec_after, remove_overlay},
+    event::{Event, EventListener},
+    id::Id,
+    keyboard::{Key, NamedKey},
+    kurbo::{Point, Vec2},
+    reactive::{create_effect, create_rw_signal, RwSignal},
+    style::{
+        Background, BorderColor, InsetBottom, InsetLeft, InsetRight, InsetTop, MarginBottom,
+        MarginLeft, MarginRight, MarginTop, Style, TextColor, Transition,
+    },
+    taffy::style::FlexDirection,
+    view::View,
+    views::{clip, container, h_stack, Decorators},
+    EventPropagation,
+};
+
+use crate::{
+    click_outside::ExtClickOutside,
+    gesture::{ExtGesture, PanPhase, DEFAULT_SLOP},
+    style::{DesignSystem, ExtDynamicColor},
+};
+
+const SLIDE: Duration = Duration::from_millis(220);
+/// Releasing a drag faster than this towards the edge closes the drawer regardless of distance
+const FLING_VELOCITY: f64 = 800.;
+
+/// The window edge a drawer slides in from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+impl Edge {
+    /// How far `v` points towards the edge, i.e. in the closing direction.
+    fn closing(self, v: Vec2) -> f64 {
+        match self {
+            Edge::Left => -v.x,
+            Edge::Right => v.x,
+            Edge::Top => -v.y,
+            Edge::Bottom => v.y,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawerMode {
+    /// Slides over the window above a backdrop
+    Modal,
+    /// Takes space next to the content and pushes it aside
+    Persistent,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DrawerOptions {
+    pub edge: Edge,
+    pub mode: DrawerMode,
+    /// Width of a side drawer or height of a top or bottom sheet
+    pub size: f64,
+    /// Close on clicks outside the panel, or on the backdrop in modal mode. Off by default in
+    /// persistent mode, where the content next to the panel is meant to stay usable.
+    pub close_on_outside: bool,
+    /// Fraction of `size` the panel has to be dragged towards its edge to close on release
+    pub dismiss_threshold: f64,
+}
+impl DrawerOptions {
+    pub fn new(edge: Edge, mode: DrawerMode) -> Self {
+        Self {
+            edge,
+            mode,
+            size: 320.,
+            close_on_outside: mode == DrawerMode::Modal,
+            dismiss_threshold: 0.35,
+        }
+    }
+
+    pub fn size(mut self, size: f64) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn close_on_outside(mut self, close_on_outside: bool) -> Self {
+        self.close_on_outside = close_on_outside;
+        self
+    }
+}
+
+/// Show `panel` at an edge of `content` while `open` is true. Modal drawers cover the window
+/// with a backdrop, persistent ones push `content` aside. Both close on Escape, by dragging the
+/// panel back towards its edge and, if enabled, on clicks outside of them.
+pub fn drawer<DS, V, CV>(
+    content: V,
+    panel: impl Fn() -> CV + 'static,
+    open: RwSignal<bool>,
+    options: DrawerOptions,
+) -> Box<dyn View>
+where
+    DS: DesignSystem + 'static,
+    V: View + 'static,
+    CV: View + 'static,
+{
+    match options.mode {
+        DrawerMode::Modal => Box::new(modal_drawer::<DS, _, _>(content, panel, open, options)),
+        DrawerMode::Persistent => {
+            Box::new(persistent_drawer::<DS, _, _>(content, panel, open, options))
+        }
+    }
+}
+
+/// A modal [`drawer`] sliding up from the bottom of the window.
+pub fn bottom_sheet<DS, V, CV>(
+    content: V,
+    panel: impl Fn() -> CV + 'static,
+    open: RwSignal<bool>,
+    height: f64,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    V: View + 'static,
+    CV: View + 'static,
+{
+    modal_drawer::<DS, _, _>(
+        content,
+        panel,
+        open,
+        DrawerOptions::new(Edge::Bottom, DrawerMode::Modal).size(height),
+    )
+}
+
+fn modal_drawer<DS, V, CV>(
+    content: V,
+    panel: impl Fn() -> CV + 'static,
+    open: RwSignal<bool>,
+    options: DrawerOptions,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    V: View + 'static,
+    CV: View + 'static,
+{
+    let panel = Rc::new(panel);
+    let overlay: Rc<Cell<Option<Id>>> = Rc::new(Cell::new(None));
+    // lags behind `open` so the panel is laid out off screen before it slides in, and stays
+    // around until it has slid out
+    let shown = create_rw_signal(false);
+
+    let layer_overlay = overlay.clone();
+    create_effect(move |_| {
+        let overlay = layer_overlay.clone();
+        if open.get() {
+            if overlay.get().is_none() {
+                let panel = panel.clone();
+                let id = add_overlay(Point::ZERO, move |_| {
+                    modal_layer::<DS, _>(panel(), open, shown, options)
+                });
+                overlay.set(Some(id));
+            }
+            exec_after(Duration::from_millis(16), move |_| {
+                if open.get_untracked() {
+                    shown.set(true);
+                }
+            });
+        } else {
+            shown.set(false);
+            exec_after(SLIDE, move |_| {
+                if !open.get_untracked() {
+                    if let Some(id) = overlay.take() {
+                        remove_overlay(id);
+                    }
+                }
+            });
+        }
+    });
+
+    container(content).on_cleanup(move || {
+        if let Some(id) = overlay.take() {
+            remove_overlay(id);
+        }
+    })
+}
+
+fn modal_layer<DS: DesignSystem + 'static, CV: View + 'static>(
+    content: CV,
+    open: RwSignal<bool>,
+    shown: RwSignal<bool>,
+    options: DrawerOptions,
+) -> impl View {
+    let panel = drawer_panel::<DS, _>(content, open, move || shown.get(), options)
+        // keep clicks on the panel from reaching the backdrop
+        .on_event(EventListener::PointerDown, |_| EventPropagation::Stop)
+        .style(move |s| {
+            let s = s.absolute();
+            match options.edge {
+                Edge::Left | Edge::Right => s.height_full().width(options.size),
+                Edge::Top | Edge::Bottom => s.width_full().height(options.size),
+            }
+        });
+
+    container(panel)
+        .on_event(EventListener::PointerDown, move |_| {
+            if options.close_on_outside {
+                open.set(false);
+            }
+            EventPropagation::Stop
+        })
+        .on_event(EventListener::PointerWheel, |_| EventPropagation::Stop)
+        .style(move |s| {
+            s.size_full()
+                .apply_if(shown.get(), |s| s.ld_color(Background, DS::BACKDROP))
+                .transition(Background, Transition::linear(SLIDE.as_secs_f64()))
+        })
+}
+
+fn persistent_drawer<DS, V, CV>(
+    content: V,
+    panel: impl Fn() -> CV + 'static,
+    open: RwSignal<bool>,
+    options: DrawerOptions,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    V: View + 'static,
+    CV: View + 'static,
+{
+    let panel = drawer_panel::<DS, _>(panel(), open, move || open.get(), options)
+        .on_click_outside(move || {
+            if options.close_on_outside && open.get_untracked() {
+                open.set(false);
+            }
+        })
+        .style(move |s| match options.edge {
+            Edge::Left | Edge::Right => s.height_full().width(options.size),
+            Edge::Top | Edge::Bottom => s.width_full().height(options.size),
+        });
+
+    let direction = match options.edge {
+        Edge::Left => FlexDirection::RowReverse,
+        Edge::Right => FlexDirection::Row,
+        Edge::Top => FlexDirection::ColumnReverse,
+        Edge::Bottom => FlexDirection::Column,
+    };
+    // the closed panel sits past the edge of the stack, so cut it off there
+    clip(
+        h_stack((container(content).style(|s| s.flex_grow(1.)), panel))
+            .style(move |s| s.size_full().flex_direction(direction)),
+    )
+    .style(|s| s.size_full())
+}
+
+/// The sliding panel shared by both modes. `on_screen` says whether it should currently be slid
+/// in; modal drawers delay it so the slide can run.
+fn drawer_panel<DS: DesignSystem + 'static, CV: View + 'static>(
+    content: CV,
+    open: RwSignal<bool>,
+    on_screen: impl Fn() -> bool + 'static,
+    options: DrawerOptions,
+) -> impl View {
+    let drag = create_rw_signal(0.);
+    let dragging = create_rw_signal(false);
+
+    let panel = container(content)
+        .keyboard_navigatable()
+        .on_event(EventListener::KeyDown, move |event| match event {
+            Event::KeyDown(key_event)
+                if key_event.key.logical_key == Key::Named(NamedKey::Escape) =>
+            {
+                open.set(false);
+                EventPropagation::Stop
+            }
+            _ => EventPropagation::Continue,
+        })
+        .on_pan(DEFAULT_SLOP, move |pan| match pan.phase {
+            PanPhase::Start | PanPhase::Update => {
+                dragging.set(true);
+                let distance = options
+                    .edge
+                    .closing(pan.translation)
+                    .clamp(0., options.size);
+                drag.set(distance);
+            }
+            PanPhase::End => {
+                dragging.set(false);
+                let far = drag.get_untracked() > options.size * options.dismiss_threshold;
+                let fast = options.edge.closing(pan.velocity) > FLING_VELOCITY;
+                if far || fast {
+                    open.set(false);
+                }
+                drag.set(0.);
+            }
+        })
+        .style(move |s| {
+            let offset = if on_screen() {
+                -drag.get()
+            } else {
+                -options.size
+            };
+            let s = s
+                .ld_color(Background, DS::BACKGROUND.base)
+                .ld_color(BorderColor, DS::BACKGROUND_3.base)
+                .ld_color(TextColor, DS::TEXT.base)
+                .padding(DS::PADDING * 2.);
+            let s = match options.edge {
+                Edge::Left => s.border_right(DS::BORDER_NORMAL),
+                Edge::Right => s.border_left(DS::BORDER_NORMAL),
+                Edge::Top => s.border_bottom(DS::BORDER_NORMAL),
+                Edge::Bottom => s.border_top(DS::BORDER_NORMAL),
+            };
+            // follow the pointer directly while dragging
+            slide(s, options, offset, !dragging.get())
+        });
+
+    let panel_id = panel.id();
+    create_effect(move |_| {
+        if open.get() {
+            exec_after(Duration::ZERO, move |_| panel_id.request_focus());
+        }
+    });
+    panel
+}
+
+/// Moves the panel `offset` pixels past its edge: negative insets for the modal overlay,
+/// negative margins in persistent mode so the content shrinks along with it.
+fn slide(s: Style, options: DrawerOptions, offset: f64, animate: bool) -> Style {
+    let transition = Transition::linear(SLIDE.as_secs_f64());
+    match (options.mode, options.edge) {
+        (DrawerMode::Modal, Edge::Left) => s
+            .inset_left(offset)
+            .apply_if(animate, |s| s.transition(InsetLeft, transition)),
+        (DrawerMode::Modal, Edge::Right) => s
+            .inset_right(offset)
+            .apply_if(animate, |s| s.transition(InsetRight, transition)),
+        (DrawerMode::Modal, Edge::Top) => s
+            .inset_top(offset)
+            .apply_if(animate, |s| s.transition(InsetTop, transition)),
+        (DrawerMode::Modal, Edge::Bottom) => s
+            .inset_bottom(offset)
+            .apply_if(animate, |s| s.transition(InsetBottom, transition)),
+        (DrawerMode::Persistent, Edge::Left) => s
+            .margin_left(offset)
+            .apply_if(animate, |s| s.transition(MarginLeft, transition)),
+        (DrawerMode::Persistent, Edge::Right) => s
+            .margin_right(offset)
+            .apply_if(animate, |s| s.transition(MarginRight, transition)),
+        (DrawerMode::Persistent, Edge::Top) => s
+            .margin_top(offset)
+            .apply_if(animate, |s| s.transition(MarginTop, transition)),
+        (DrawerMode::Persistent, Edge::Bottom) => s
+            .margin_bottom(offset)
+            .apply_if(animate, |s| s.transition(MarginBottom, transition)),
+    }
+}
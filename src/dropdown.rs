@@ -1,295 +1,1076 @@
-// use floem::{
-//     reactive::{create_effect, create_rw_signal, use_context, ReadSignal, RwSignal},
-//     style::{Display, JustifyContent, Position, Style, TextOverflow},
-//     unit::UnitExt,
-//     view::View,
-//     views::*,
-// };
-
-// use crate::{
-//     components::scrollable_dropdown,
-//     icons::*,
-//     style::{BorderWidths, ColorPalette, LightDark, PopOver, ResponsiveColor, WindowClicked},
-// };
-
-// #[derive(Clone)]
-// pub struct DropDownStyles {
-//     pub main_box_back: LightDark,
-//     pub main_box_border: LightDark,
-//     pub arrow: ResponsiveColor,
-//     pub button_cont: ResponsiveColor,
-//     pub drop_box_back: LightDark,
-//     pub drop_box_border: LightDark,
-//     pub sel_cont: ResponsiveColor,
-//     pub scroll_height: Option<f32>,
-//     pub max_scroll_height: Option<f32>,
-// }
-// impl Default for DropDownStyles {
-//     fn default() -> Self {
-//         let accent = ResponsiveColor::from_lightdark(
-//             ColorPalette::ACCENT_LD
-//                 .dark_decrease_cap_light(10)
-//                 .light_increase_cap_light(10),
-//         )
-//         .reduce_active_to_hover();
-//         let arrow = ResponsiveColor::from_lightdark(LightDark::default().reverse());
-//         Self {
-//             main_box_back: LightDark::new(ColorPalette::TRANSPARENT, ColorPalette::TRANSPARENT),
-//             main_box_border: ColorPalette::BORDER_LD,
-//             button_cont: accent.clone().disable_base(),
-//             drop_box_back: LightDark::new(ColorPalette::LIGHT2, ColorPalette::DARK1),
-//             drop_box_border: LightDark::new(ColorPalette::LIGHT3, ColorPalette::DARK3).reverse(),
-//             sel_cont: accent,
-//             scroll_height: Some(20. * 10.),
-//             max_scroll_height: None,
-//             arrow,
-//         }
-//     }
-// }
-
-// #[derive(Clone, Copy)]
-// enum ArrowSelect {
-//     TopLeft,
-//     BottomLeft,
-//     Right,
-// }
-
-// pub fn dropdown<SAPF, MAPF, V, V2, V3>(
-//     data: ReadSignal<im::Vector<String>>,
-//     name_icon: impl Fn() -> V,
-//     main_apperance: MAPF,
-//     scroll_apperance: SAPF,
-//     on_select: impl Fn(String) + 'static + Copy,
-//     default_val: Option<String>,
-//     styles: ReadSignal<DropDownStyles>,
-// ) -> impl View
-// where
-//     V: View + 'static,
-//     V2: View + 'static,
-//     V3: View + 'static,
-//     MAPF: (Fn(Box<dyn Fn() -> String>) -> V2) + 'static + Copy,
-//     SAPF: (Fn(String) -> V3) + 'static + Copy,
-// {
-//     let window_clicked = use_context::<WindowClicked>()
-//         .expect("Expected there to be a global window clicked getter");
-//     let pop_over =
-//         use_context::<PopOver>().expect("Expected a PopOver trigger to have been provided");
-
-//     let display_scroll = create_rw_signal(false);
-//     let inner_text_idx = create_rw_signal(0);
-//     let (box_func, box_size) = crate::style::lazy_size();
-
-//     create_effect(move |_| {
-//         window_clicked.track();
-//         pop_over.track();
-//         display_scroll.update(|val| *val = false);
-//     });
-
-//     if let Some(default_val) = default_val {
-//         inner_text_idx.update(|val| {
-//             *val = data.with(|list| {
-//                 list.iter()
-//                     .position(|inner| inner == &default_val)
-//                     .unwrap_or(*val)
-//             })
-//         })
-//     }
-//     let (main_box_border_radius_fn, main_box_border_radius) = crate::style::lazy_border_rad();
-
-//     container({
-//         stack({
-//             (
-//                 // everything but the dropdown
-//                 stack({
-//                     (
-//                         // icon
-//                         name_icon(),
-//                         // hstack - continuous box w/ buttons
-//                         stack({
-//                             (
-//                                 up_down_buttons(inner_text_idx, data, on_select, styles),
-//                                 // Text font size input
-//                                 main_apperance(Box::new(move || {
-//                                     data.get().get(inner_text_idx.get()).unwrap().to_string()
-//                                 }))
-//                                 .style(move |base| {
-//                                     base.padding_left(2.px())
-//                                         .text_overflow(TextOverflow::Ellipsis)
-//                                 }),
-//                                 // Down arrow on far right
-//                                 arrow_and_container(ArrowSelect::Right, styles, move || {
-//                                     let state = display_scroll.get();
-//                                     pop_over.notify();
-//                                     display_scroll.update(|val| *val = !state);
-//                                 }),
-//                             )
-//                         })
-//                         .on_resize(main_box_border_radius_fn)
-//                         .style(move |base| {
-//                             base.flex_row()
-//                                 .border(BorderWidths::SM)
-//                                 .border_radius(main_box_border_radius())
-//                                 .items_center()
-//                                 .justify_content(Some(JustifyContent::SpaceBetween))
-//                                 .size_pct(100.0, 100.0)
-//                                 .border_color(styles.with(|val| val.main_box_border.color()))
-//                                 .background(styles.with(|val| val.main_box_back.color()))
-//                         }),
-//                     )
-//                 })
-//                 .on_resize(box_func)
-//                 .style(|base| base.flex_row().items_start().size_pct(100.0, 100.0)),
-//                 scrollable_dropdown(
-//                     move || data.get(),
-//                     scroll_apperance,
-//                     move || styles.get().drop_box_back,
-//                     move || styles.get().sel_cont,
-//                     move |item| {
-//                         inner_text_idx.update(move |val| {
-//                             *val = data
-//                                 .get()
-//                                 .iter()
-//                                 .position(|inner| inner == &item)
-//                                 .unwrap_or(*val);
-//                             display_scroll.update(|val| *val = false);
-//                             on_select(item.clone());
-//                         });
-//                     },
-//                 )
-//                 .style(move |base| {
-//                     base.apply_if(!display_scroll.get(), |s| {
-//                         s.display(Display::None).height(0.).width(0.)
-//                     })
-//                     .z_index(1)
-//                     .position(Position::Absolute)
-//                     .width_pct(70.)
-//                     .apply_opt(styles.get().scroll_height, |style, val| style.height(val))
-//                     .apply_opt(styles.get().max_scroll_height, |style, val| {
-//                         style.max_height(val)
-//                     })
-//                     .inset_top(box_size().height as f32 + 3.)
-//                     .border(2.)
-//                     .border_color(styles.with(|val| val.drop_box_border.color()))
-//                     .border_radius(main_box_border_radius())
-//                 }),
-//             )
-//         })
-//         .style(|base| base.flex_col().items_center().size_pct(100.0, 100.0))
-//     })
-// }
-
-// fn arrow_and_container(
-//     selector: ArrowSelect,
-//     styles: ReadSignal<DropDownStyles>,
-//     on_click: impl Fn() + 'static,
-// ) -> impl View {
-//     let active = create_rw_signal(false);
-//     container({
-//         match selector {
-//             ArrowSelect::TopLeft => icon_chevron_up(),
-//             ArrowSelect::BottomLeft | ArrowSelect::Right => icon_chevron_down(),
-//         }
-//         .style(move |_| DPStyles::button_style(styles, active))
-//         .hover_style(move |_| {
-//             DPStyles::button_style(styles, active).color(styles.with(|val| val.arrow.hover.color()))
-//         })
-//         .active_style(move |_| {
-//             DPStyles::button_style(styles, active)
-//                 .color(styles.with(|val| val.arrow.active.color()))
-//         })
-//     })
-//     .style(move |_| {
-//         DPStyles::button_cont_style(selector)
-//             .background(styles.with(|val| val.button_cont.base.color()))
-//     })
-//     .hover_style(move |_| {
-//         DPStyles::button_cont_style(selector)
-//             .background(styles.with(|val| val.button_cont.hover.color()))
-//     })
-//     .active_style(move |_| {
-//         DPStyles::button_cont_style(selector)
-//             .background(styles.with(|val| val.button_cont.active.color()))
-//     })
-//     .on_event(floem::event::EventListener::PointerDown, move |_| {
-//         active.update(|val| *val = true);
-//         true
-//     })
-//     .on_event(floem::event::EventListener::PointerUp, move |_| {
-//         active.update(|val| *val = false);
-//         on_click();
-//         true
-//     })
-// }
-
-// fn increment_and_select(
-//     selector: ArrowSelect,
-//     inner_text_idx: RwSignal<usize>,
-//     data: ReadSignal<im::Vector<String>>,
-//     on_select: impl Fn(String) + 'static + Copy,
-// ) -> bool {
-//     inner_text_idx.update(|val| {
-//         let new_val = if let ArrowSelect::TopLeft = selector {
-//             val.checked_sub(1).unwrap_or(*val)
-//         } else {
-//             *val + 1
-//         };
-//         if new_val < data.get().len() {
-//             *val = new_val;
-//         }
-//         on_select(data.get().get(*val).unwrap().to_string());
-//     });
-//     true
-// }
-
-// fn up_down_buttons(
-//     inner_text_idx: RwSignal<usize>,
-//     data: ReadSignal<im::Vector<String>>,
-//     on_select: impl Fn(String) + 'static + Copy,
-//     styles: ReadSignal<DropDownStyles>,
-// ) -> impl View {
-//     stack({
-//         (
-//             // First Button
-//             arrow_and_container(ArrowSelect::TopLeft, styles, move || {
-//                 increment_and_select(ArrowSelect::TopLeft, inner_text_idx, data, on_select);
-//             }),
-//             // Secong Button
-//             arrow_and_container(ArrowSelect::BottomLeft, styles, move || {
-//                 increment_and_select(ArrowSelect::BottomLeft, inner_text_idx, data, on_select);
-//             }),
-//         )
-//     })
-//     .style(|base| base.flex_col().items_center())
-// }
-
-// pub struct DPStyles {}
-// impl DPStyles {
-//     const ICW: f64 = 15.;
-//     pub const SCROLL_CONTAINER_PADDING: f64 = 6.;
-
-//     fn button_style(styles: ReadSignal<DropDownStyles>, _active: RwSignal<bool>) -> Style {
-//         let color = styles.with(|val| val.arrow.base.color());
-//         Style::BASE.size(Self::ICW, Self::ICW).color(color)
-//         // .apply_if(active.get(), |s| s.inset_top_px(0.2))
-//         // .apply_if(!active.get(), |s| s.inset_top(LengthPercentageAuto::Auto))
-//     }
-
-//     fn button_cont_style(selector: ArrowSelect) -> Style {
-//         let horiz_padding = 5.px();
-//         Style::BASE
-//             .height(100.0.pct())
-//             // .width(horiz_padding * 2. + Self::ICW)
-//             .width(horiz_padding)
-//             .items_center()
-//             .justify_center()
-//             .padding_horiz(horiz_padding)
-//             .padding_top(match selector {
-//                 ArrowSelect::TopLeft => 1.,
-//                 _ => 0.,
-//             })
-//             .padding_bottom(match selector {
-//                 ArrowSelect::BottomLeft => 1.,
-//                 _ => 0.,
-//             })
-//     }
-// }
+use std::{cell::RefCell, collections::HashMap, hash::Hash, rc::Rc};
+
+use floem::{
+    kurbo::{Point, Rect},
+    reactive::{create_effect, create_rw_signal, ReadSignal, RwSignal},
+    style::{Background, BorderColor, Display, Style, TextColor, TextOverflow},
+    view::View,
+    views::{
+        self, container, empty, h_stack, scroll, v_stack, virtual_list, Decorators, Svg,
+        VirtualListDirection, VirtualListItemSize,
+    },
+};
+
+use crate::{
+    click_outside::ExtClickOutside,
+    gesture::{ExtGesture, DEFAULT_REPEAT_DELAY, DEFAULT_REPEAT_INTERVAL},
+    handlers::now,
+    icons::*,
+    keymap::KeyPress,
+    list_nav::{next_enabled, page_enabled, TypeAhead},
+    pop_over::{pop_over, Align, Placement, Side},
+    style::{
+        DesignSystem, EventHandlers, ExtAnyEvent, ExtDynamicColor, LightDark, ResponsiveColor,
+    },
+};
+
+#[derive(Clone)]
+pub struct DropDownStyles {
+    pub main_box_back: LightDark,
+    pub main_box_border: LightDark,
+    pub arrow: ResponsiveColor,
+    pub button_cont: ResponsiveColor,
+    pub drop_box_back: LightDark,
+    pub drop_box_border: LightDark,
+    pub sel_cont: ResponsiveColor,
+    pub scroll_height: Option<f32>,
+    pub max_scroll_height: Option<f32>,
+    pub row_height: RowHeight,
+}
+impl DropDownStyles {
+    /// The default look, with colors taken from `DS`.
+    pub fn new<DS: DesignSystem>() -> Self {
+        let accent = DS::ACCENT.reduce_active_to_hover();
+        Self {
+            main_box_back: LightDark::transparent(),
+            main_box_border: DS::BACKGROUND_3.base,
+            arrow: DS::TEXT,
+            button_cont: accent.clone().disable_base(),
+            drop_box_back: DS::BACKGROUND_EXT.base,
+            drop_box_border: DS::BACKGROUND_3.base,
+            sel_cont: accent,
+            scroll_height: Some(20. * 10.),
+            max_scroll_height: None,
+            row_height: RowHeight::Measured {
+                estimate: (DS::BASE_FONT_SIZE * 1.6) as f64,
+            },
+        }
+    }
+}
+
+/// How tall the rows of a dropdown list are. Only the rows in view are built, so the list has
+/// to know where the others would be.
+#[derive(Clone, Copy, Debug)]
+pub enum RowHeight {
+    Fixed(f64),
+    /// Rows are measured once they have been shown; `estimate` stands in for the rest
+    Measured {
+        estimate: f64,
+    },
+}
+
+/// Row positions for a virtualized list.
+#[derive(Clone, Copy)]
+pub(crate) struct RowMetrics {
+    height: RowHeight,
+    measured: RwSignal<HashMap<usize, f64>>,
+    /// The top of every row so far, extended as far as asked for and cut back to the first
+    /// row whose height changed
+    tops: RwSignal<Vec<f64>>,
+}
+impl RowMetrics {
+    pub(crate) fn new(height: RowHeight) -> Self {
+        Self {
+            height,
+            measured: create_rw_signal(HashMap::new()),
+            tops: create_rw_signal(vec![0.]),
+        }
+    }
+
+    /// Run `f` on the tops of the rows up to `len`, the last one being the bottom of row
+    /// `len - 1`.
+    fn with_tops<R>(&self, len: usize, estimate: f64, f: impl FnOnce(&[f64]) -> R) -> R {
+        if self.tops.with_untracked(|tops| tops.len()) <= len {
+            self.measured.with_untracked(|measured| {
+                self.tops.update(|tops| {
+                    while tops.len() <= len {
+                        let idx = tops.len() - 1;
+                        let height = measured.get(&idx).copied().unwrap_or(estimate);
+                        tops.push(tops[idx] + height);
+                    }
+                })
+            });
+        }
+        self.tops.with_untracked(|tops| f(&tops[..=len]))
+    }
+
+    pub(crate) fn height_of(&self, idx: usize) -> f64 {
+        match self.height {
+            RowHeight::Fixed(height) => height,
+            RowHeight::Measured { estimate } => self
+                .measured
+                .with(|measured| measured.get(&idx).copied().unwrap_or(estimate)),
+        }
+    }
+
+    pub(crate) fn top_of(&self, idx: usize) -> f64 {
+        match self.height {
+            RowHeight::Fixed(height) => height * idx as f64,
+            RowHeight::Measured { estimate } => self.with_tops(idx, estimate, |tops| tops[idx]),
+        }
+    }
+
+    /// The row covering the vertical offset `y`.
+    pub(crate) fn index_at(&self, y: f64, len: usize) -> usize {
+        let last = len.saturating_sub(1);
+        match self.height {
+            RowHeight::Fixed(height) => ((y / height.max(1.)) as usize).min(last),
+            RowHeight::Measured { estimate } => self.with_tops(len, estimate, |tops| {
+                tops[1..].partition_point(|bottom| *bottom <= y).min(last)
+            }),
+        }
+    }
+
+    pub(crate) fn measure(&self, idx: usize, height: f64) {
+        if let RowHeight::Measured { .. } = self.height {
+            let known = self
+                .measured
+                .with_untracked(|measured| measured.get(&idx).copied());
+            if known != Some(height) {
+                self.measured.update(|measured| {
+                    measured.insert(idx, height);
+                });
+                self.tops.update(|tops| tops.truncate(idx + 1));
+            }
+        }
+    }
+
+    /// Sizes for a `virtual_list` over `(position, item)` pairs.
+    pub(crate) fn item_size<T>(self) -> VirtualListItemSize<(usize, T)> {
+        match self.height {
+            RowHeight::Fixed(height) => VirtualListItemSize::Fixed(Box::new(move || height)),
+            RowHeight::Measured { .. } => {
+                VirtualListItemSize::Fn(Box::new(move |(idx, _)| self.height_of(*idx)))
+            }
+        }
+    }
+
+    /// Measurements belong to positions, so they are dropped when the items change.
+    pub(crate) fn clear(&self) {
+        self.measured.update(|measured| measured.clear());
+        self.tops.update(|tops| tops.truncate(1));
+    }
+
+    /// Where to scroll so row `idx` is fully inside `viewport`, if it isn't already.
+    pub(crate) fn reveal(&self, idx: usize, viewport: Rect) -> Option<Point> {
+        let top = self.top_of(idx);
+        let bottom = top + self.height_of(idx);
+        if top < viewport.y0 {
+            Some(Point::new(viewport.x0, top))
+        } else if bottom > viewport.y1 {
+            Some(Point::new(viewport.x0, bottom - viewport.height()))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum ArrowSelect {
+    TopLeft,
+    BottomLeft,
+    Right,
+}
+
+/// Where a [`typed_dropdown`] keeps its selection.
+pub enum Selection<K: 'static> {
+    /// The key of the selected item, as returned by the key function
+    Key(RwSignal<Option<K>>),
+    /// The position of the selected item in the list
+    Index(RwSignal<Option<usize>>),
+}
+
+/// A dropdown over strings. The selection is tracked by position, so duplicate entries are
+/// fine.
+pub fn dropdown<DS, SAPF, MAPF, V, V2, V3>(
+    data: ReadSignal<im::Vector<String>>,
+    name_icon: impl Fn() -> V,
+    main_apperance: MAPF,
+    scroll_apperance: SAPF,
+    on_select: impl Fn(String) + 'static + Copy,
+    default_val: Option<String>,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    V: View + 'static,
+    V2: View + 'static,
+    V3: View + 'static,
+    MAPF: (Fn(Box<dyn Fn() -> String>) -> V2) + 'static + Copy,
+    SAPF: (Fn(String) -> V3) + 'static + Copy,
+{
+    let default_idx = default_val
+        .and_then(|default_val| {
+            data.with_untracked(|list| list.iter().position(|inner| inner == &default_val))
+        })
+        .unwrap_or(0);
+
+    typed_dropdown::<DS, _, _, _, _, _>(
+        data,
+        |item: &String| item.clone(),
+        |item: &String| item.clone(),
+        Selection::Index(create_rw_signal(Some(default_idx))),
+        name_icon,
+        move |current| main_apperance(Box::new(move || current().unwrap_or_default())),
+        scroll_apperance,
+        on_select,
+        styles,
+    )
+}
+
+/// A dropdown over any item type. `key` identifies items for [`Selection::Key`] and should be
+/// unique, `label` is matched against typed characters, `main_view` renders the main box from
+/// the current selection and `row_view` renders one entry of the list.
+///
+/// With the main box focused, Up/Down/Home/End/PageUp/PageDown move through the open list,
+/// Enter commits, Escape closes it without changing the selection and typing jumps to the next
+/// item starting with the typed text.
+#[allow(clippy::too_many_arguments)]
+pub fn typed_dropdown<DS, T, K, V, V2, V3>(
+    items: ReadSignal<im::Vector<T>>,
+    key: impl Fn(&T) -> K + Copy + 'static,
+    label: impl Fn(&T) -> String + Copy + 'static,
+    selection: Selection<K>,
+    name_icon: impl Fn() -> V,
+    main_view: impl FnOnce(Box<dyn Fn() -> Option<T>>) -> V2,
+    row_view: impl Fn(T) -> V3 + Copy + 'static,
+    on_select: impl Fn(T) + Copy + 'static,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    T: Clone + 'static,
+    K: Clone + Hash + Eq + 'static,
+    V: View + 'static,
+    V2: View + 'static,
+    V3: View + 'static,
+{
+    dropdown_core::<DS, _, _, _, _, _>(
+        items,
+        move |item| Some(key(item)),
+        label,
+        |_| true,
+        |_| None,
+        selection,
+        name_icon,
+        main_view,
+        row_view,
+        on_select,
+        styles,
+    )
+}
+
+/// The open state, keyboard highlight and scrolling of a virtualized dropdown list, shared by
+/// the dropdowns and the combobox.
+#[derive(Clone, Copy)]
+pub(crate) struct ListControls {
+    /// Whether the list is shown
+    pub(crate) open: RwSignal<bool>,
+    /// The row the keyboard is on while the list is open
+    pub(crate) highlighted: RwSignal<Option<usize>>,
+    pub(crate) metrics: RowMetrics,
+    /// The visible part of the list, in list coordinates
+    pub(crate) viewport: RwSignal<Rect>,
+    pub(crate) scroll_request: RwSignal<Option<Point>>,
+}
+impl ListControls {
+    /// `key` identifies the row at the top of the list, which stays in place when `items` change.
+    pub(crate) fn new<T, K>(
+        items: ReadSignal<im::Vector<T>>,
+        key: impl Fn(&T) -> Option<K> + Copy + 'static,
+        row_height: RowHeight,
+    ) -> Self
+    where
+        T: Clone + 'static,
+        K: Clone + PartialEq + 'static,
+    {
+        let highlighted: RwSignal<Option<usize>> = create_rw_signal(None);
+        let metrics = RowMetrics::new(row_height);
+        let viewport = create_rw_signal(Rect::ZERO);
+        let scroll_request: RwSignal<Option<Point>> = create_rw_signal(None);
+
+        // bring rows reached with the keyboard into view, even if they haven't been built yet
+        create_effect(move |_| {
+            if let Some(idx) = highlighted.get() {
+                if let Some(point) = metrics.reveal(idx, viewport.get_untracked()) {
+                    scroll_request.set(Some(point));
+                }
+            }
+        });
+        // keep the row at the top of the list in place when the items change around it
+        create_effect(move |previous: Option<im::Vector<Option<K>>>| {
+            let keys: im::Vector<Option<K>> = items.with(|list| list.iter().map(key).collect());
+            if let Some(previous) = previous {
+                let view = viewport.get_untracked();
+                let top = metrics.index_at(view.y0, previous.len());
+                metrics.clear();
+                let found = previous.get(top).and_then(|top_key| keys.index_of(top_key));
+                if let Some(idx) = found {
+                    scroll_request.set(Some(Point::new(view.x0, metrics.top_of(idx))));
+                }
+            }
+            keys
+        });
+
+        Self {
+            open: create_rw_signal(false),
+            highlighted,
+            metrics,
+            viewport,
+            scroll_request,
+        }
+    }
+
+    /// Move the highlight for Up/Down/Home/End/PageUp/PageDown, skipping rows that aren't
+    /// `enabled`. Returns whether `key` was one of them.
+    pub(crate) fn navigate(&self, key: &str, len: usize, enabled: impl Fn(usize) -> bool) -> bool {
+        let from = self.highlighted.get_untracked();
+        let page = || {
+            let row = self.metrics.height_of(from.unwrap_or(0));
+            let rows = self.viewport.get_untracked().height() / row.max(1.);
+            (rows.floor() as isize).max(1)
+        };
+        let target = match key {
+            "down" => next_enabled(from, len, 1, false, &enabled),
+            "up" => next_enabled(from, len, -1, false, &enabled),
+            "home" => next_enabled(None, len, 1, false, &enabled),
+            "end" => next_enabled(None, len, -1, false, &enabled),
+            "pagedown" => page_enabled(from, len, page(), &enabled),
+            "pageup" => page_enabled(from, len, -page(), &enabled),
+            _ => return false,
+        };
+        self.highlighted.set(target);
+        true
+    }
+}
+
+/// The state and input handling of a dropdown, apart from its views. The views install the
+/// handlers made here, so a [`HeadlessTree`](crate::record::HeadlessTree) can replay recorded
+/// input against the same logic without a window.
+#[derive(Clone)]
+pub(crate) struct DropdownControls {
+    pub(crate) list: ListControls,
+    pub(crate) selected: RwSignal<Option<usize>>,
+    commit: Rc<dyn Fn(usize)>,
+    key_press: Rc<dyn Fn(&KeyPress) -> bool>,
+}
+impl DropdownControls {
+    /// Rows that aren't `selectable` are skipped by the keyboard and can't be committed.
+    pub(crate) fn new<T, K>(
+        items: ReadSignal<im::Vector<T>>,
+        key: impl Fn(&T) -> Option<K> + Copy + 'static,
+        label: impl Fn(&T) -> String + Copy + 'static,
+        selectable: impl Fn(&T) -> bool + Copy + 'static,
+        selection: Selection<K>,
+        on_select: impl Fn(T) + Copy + 'static,
+        row_height: RowHeight,
+    ) -> Self
+    where
+        T: Clone + 'static,
+        K: Clone + Hash + Eq + 'static,
+    {
+        let nav = ListControls::new(items, key, row_height);
+        let ListControls {
+            open, highlighted, ..
+        } = nav;
+        let selected = selection_index(items, key, selection);
+        let enabled =
+            move |idx: usize| items.with_untracked(|list| list.get(idx).is_some_and(selectable));
+        let type_ahead = Rc::new(RefCell::new(TypeAhead::default()));
+
+        create_effect(move |_| {
+            if open.get() {
+                highlighted.set(selected.get_untracked());
+            }
+        });
+
+        let commit = move |idx: usize| {
+            if !enabled(idx) {
+                return;
+            }
+            if let Some(item) = items.with_untracked(|list| list.get(idx).cloned()) {
+                selected.set(Some(idx));
+                on_select(item);
+            }
+            open.set(false);
+        };
+
+        let key_press = move |press: &KeyPress| {
+            let is_open = open.get_untracked();
+            let len = items.with_untracked(|list| list.len());
+            if is_open && nav.navigate(&press.key, len, enabled) {
+                return true;
+            }
+            let from = highlighted.get_untracked();
+            match press.key.as_str() {
+                "down" | "up" | "enter" | "space" if !is_open => open.set(true),
+                "enter" => match from {
+                    Some(idx) => commit(idx),
+                    None => open.set(false),
+                },
+                "esc" if is_open => open.set(false),
+                text if text.chars().count() == 1
+                    && !(press.mods.ctrl || press.mods.alt || press.mods.meta) =>
+                {
+                    let mut type_ahead = type_ahead.borrow_mut();
+                    type_ahead.push(text, now());
+                    let current = if is_open {
+                        from
+                    } else {
+                        selected.get_untracked()
+                    };
+                    let found = items.with_untracked(|list| {
+                        type_ahead.find(current, len, |idx| label(&list[idx]), enabled)
+                    });
+                    match found {
+                        // a closed dropdown selects right away, like a native select
+                        Some(idx) if !is_open => commit(idx),
+                        found => highlighted.set(found.or(from)),
+                    }
+                }
+                _ => return false,
+            }
+            true
+        };
+
+        Self {
+            list: nav,
+            selected,
+            commit: Rc::new(commit),
+            key_press: Rc::new(key_press),
+        }
+    }
+
+    pub(crate) fn toggle(&self) {
+        self.list.open.update(|open| *open = !*open);
+    }
+
+    /// Keyboard handling for the main box.
+    pub(crate) fn main_box_handlers(&self) -> EventHandlers {
+        let key_press = self.key_press.clone();
+        EventHandlers::new().on_key_press(move |press| key_press(press))
+    }
+
+    /// Clicking row `idx` commits it.
+    pub(crate) fn row_handlers(&self, idx: usize) -> EventHandlers {
+        let commit = self.commit.clone();
+        EventHandlers::new().on_click(move |_| {
+            commit(idx);
+            true
+        })
+    }
+}
+
+/// The scrolling rows of a dropdown list, of which only the ones in view are built. `row_view`
+/// gets each item with its position, `key` tells rows apart when the items change and `style`
+/// sizes the scroll area.
+pub(crate) fn dropdown_rows<T, K, V>(
+    items: ReadSignal<im::Vector<T>>,
+    key: impl Fn(&T) -> K + Copy + 'static,
+    list: ListControls,
+    row_view: impl Fn(usize, T) -> V + 'static,
+    style: impl Fn(Style, &DropDownStyles) -> Style + 'static,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    T: Clone + 'static,
+    K: Hash + Eq + 'static,
+    V: View + Decorators + 'static,
+{
+    let metrics = list.metrics;
+    // numbered once per change of the items, not on every scroll
+    let rows = create_rw_signal(im::Vector::new());
+    create_effect(move |_| rows.set(items.get().into_iter().enumerate().collect()));
+    scroll(
+        virtual_list(
+            VirtualListDirection::Vertical,
+            metrics.item_size(),
+            move || rows.get(),
+            // the position keeps rows apart even if two items share a key
+            move |(idx, item)| (*idx, key(item)),
+            move |(idx, item)| {
+                row_view(idx, item).on_resize(move |rect| metrics.measure(idx, rect.height()))
+            },
+        )
+        .style(|s| s.flex_col().width_full()),
+    )
+    .on_scroll(move |rect| list.viewport.set(rect))
+    .scroll_to(move || list.scroll_request.get())
+    .style(move |s| styles.with(|styles| style(s.width_full(), styles)))
+}
+
+/// The dropdown behind [`typed_dropdown`] and [`grouped_dropdown`]. Rows that aren't
+/// `selectable` are skipped by the keyboard and ignore clicks. The `group` title of the row at
+/// the top of the list is pinned above it as a sticky header.
+#[allow(clippy::too_many_arguments)]
+fn dropdown_core<DS, T, K, V, V2, V3>(
+    items: ReadSignal<im::Vector<T>>,
+    key: impl Fn(&T) -> Option<K> + Copy + 'static,
+    label: impl Fn(&T) -> String + Copy + 'static,
+    selectable: impl Fn(&T) -> bool + Copy + 'static,
+    group: impl Fn(&T) -> Option<String> + Copy + 'static,
+    selection: Selection<K>,
+    name_icon: impl Fn() -> V,
+    main_view: impl FnOnce(Box<dyn Fn() -> Option<T>>) -> V2,
+    row_view: impl Fn(T) -> V3 + Copy + 'static,
+    on_select: impl Fn(T) + Copy + 'static,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    T: Clone + 'static,
+    K: Clone + Hash + Eq + 'static,
+    V: View + 'static,
+    V2: View + 'static,
+    V3: View + 'static,
+{
+    let enabled =
+        move |idx: usize| items.with_untracked(|list| list.get(idx).is_some_and(selectable));
+    let (box_func, box_size) = crate::style::lazy_size();
+    let (main_box_border_radius_fn, main_box_border_radius) = crate::style::lazy_border_rad();
+
+    let controls = DropdownControls::new(
+        items,
+        key,
+        label,
+        selectable,
+        selection,
+        on_select,
+        styles.with_untracked(|val| val.row_height),
+    );
+    let ListControls {
+        open: display_scroll,
+        highlighted,
+        metrics,
+        viewport,
+        ..
+    } = controls.list;
+    let selected = controls.selected;
+
+    // the main box previews the highlighted row until it is committed or the list is closed
+    let current = move || {
+        let shown = if display_scroll.get() {
+            highlighted.get().or(selected.get())
+        } else {
+            selected.get()
+        };
+        shown.and_then(|idx| items.with(|list| list.get(idx).cloned()))
+    };
+
+    // hstack - continuous box w/ buttons
+    let main_box = h_stack((
+        stepper_buttons::<DS>(styles, move |step| {
+            increment_and_select(step, selected, items, enabled, on_select)
+        }),
+        main_view(Box::new(current)).style(|s| {
+            s.padding_left(2.)
+                .flex_grow(1.)
+                .text_overflow(TextOverflow::Ellipsis)
+        }),
+        // Down arrow on far right
+        arrow_and_container::<DS>(ArrowSelect::Right, styles, {
+            let controls = controls.clone();
+            move || controls.toggle()
+        }),
+    ))
+    .keyboard_navigatable()
+    .event_handlers(controls.main_box_handlers())
+    .on_resize(move |rect| {
+        main_box_border_radius_fn(rect);
+        box_func(rect);
+    })
+    .style(move |s| {
+        let styles = styles.get();
+        s.items_center()
+            .justify_between()
+            .size_full()
+            .border(DS::BORDER_SMALL)
+            .border_radius(main_box_border_radius() as f64)
+            .ld_color(BorderColor, styles.main_box_border)
+            .ld_color(Background, styles.main_box_back)
+            .focus_visible(|s| s.border(DS::BORDER_NORMAL))
+    });
+
+    // the title of the group the top row of the list belongs to
+    let sticky_header = move || {
+        let top = metrics.index_at(viewport.get().y0, items.with(|list| list.len()));
+        items.with(|list| list.get(top).and_then(group))
+    };
+
+    let drop_box = move || {
+        let controls = controls.clone();
+        let rows = dropdown_rows(
+            items,
+            key,
+            controls.list,
+            move |idx, item| {
+                let can_select = selectable(&item);
+                container(row_view(item))
+                    .event_handlers(controls.row_handlers(idx))
+                    .style(move |s| {
+                        let sel_cont = styles.with(|val| val.sel_cont.clone());
+                        s.width_full()
+                            .padding_horiz(DPStyles::SCROLL_CONTAINER_PADDING)
+                            .apply_if(can_select, |s| {
+                                s.dynamic_color(Background, sel_cont.clone().disable_base())
+                            })
+                            .apply_if(highlighted.get() == Some(idx), |s| {
+                                s.ld_color(Background, sel_cont.hover)
+                            })
+                            .apply_if(selected.get() == Some(idx), |s| {
+                                s.ld_color(Background, sel_cont.base)
+                            })
+                    })
+            },
+            |s, styles| {
+                s.apply_opt(styles.scroll_height, |s, val| s.height(val))
+                    .apply_opt(styles.max_scroll_height, |s, val| s.max_height(val))
+            },
+            styles,
+        );
+
+        let sticky = views::label(move || sticky_header().unwrap_or_default()).style(move |s| {
+            s.absolute()
+                .inset_top(0.)
+                .width_full()
+                .padding_horiz(DPStyles::SCROLL_CONTAINER_PADDING)
+                .font_size(DS::BASE_FONT_SIZE * 0.85)
+                .ld_color(TextColor, DS::TEXT.base.set_alpha(70))
+                .ld_color(Background, styles.with(|val| val.drop_box_back))
+                .apply_if(sticky_header().is_none(), |s| s.display(Display::None))
+        });
+
+        v_stack((rows, sticky)).style(move |s| {
+            let styles = styles.get();
+            s.min_width(box_size().width)
+                .border(DS::BORDER_NORMAL)
+                .border_radius(main_box_border_radius() as f64)
+                .ld_color(BorderColor, styles.drop_box_border)
+                .ld_color(Background, styles.drop_box_back)
+        })
+    };
+
+    h_stack((
+        // icon
+        name_icon(),
+        pop_over(
+            main_box,
+            drop_box,
+            display_scroll,
+            Placement::new(Side::Bottom, Align::Start).offset(3.),
+        )
+        // clicks on the list count as inside since the popover belongs to this view
+        .on_click_outside(move || display_scroll.set(false))
+        .style(|s| s.flex_grow(1.).height_full()),
+    ))
+    .style(|s| s.items_center().size_full())
+}
+
+/// A [`typed_dropdown`] showing `label` in the main box and in every row.
+pub fn label_dropdown<DS, T, K>(
+    items: ReadSignal<im::Vector<T>>,
+    key: impl Fn(&T) -> K + Copy + 'static,
+    label: impl Fn(&T) -> String + Copy + 'static,
+    selection: Selection<K>,
+    on_select: impl Fn(T) + Copy + 'static,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    T: Clone + 'static,
+    K: Clone + Hash + Eq + 'static,
+{
+    typed_dropdown::<DS, _, _, _, _, _>(
+        items,
+        key,
+        label,
+        selection,
+        empty,
+        move |current| views::label(move || current().map(|item| label(&item)).unwrap_or_default()),
+        move |item| views::label(move || label(&item)),
+        on_select,
+        styles,
+    )
+}
+
+/// One option of a [`grouped_dropdown`].
+#[derive(Clone)]
+pub struct DropdownItem<T> {
+    pub value: T,
+    pub label: String,
+    /// Dimmer text after the label, e.g. a shortcut or a count
+    pub secondary: Option<String>,
+    pub icon: Option<fn() -> Svg>,
+    /// Draw the label in this font family instead of the list's, e.g. to preview fonts
+    pub font_family: Option<String>,
+    /// Shown but can't be selected
+    pub disabled: bool,
+}
+impl<T> DropdownItem<T> {
+    pub fn new(value: T, label: impl Into<String>) -> Self {
+        Self {
+            value,
+            label: label.into(),
+            secondary: None,
+            icon: None,
+            font_family: None,
+            disabled: false,
+        }
+    }
+
+    pub fn secondary(mut self, text: impl Into<String>) -> Self {
+        self.secondary = Some(text.into());
+        self
+    }
+
+    pub fn icon(mut self, icon: fn() -> Svg) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub fn font_family(mut self, family: impl Into<String>) -> Self {
+        self.font_family = Some(family.into());
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+}
+
+/// The tree of options a [`grouped_dropdown`] is built from. Groups can be nested.
+#[derive(Clone)]
+pub enum DropdownEntry<T> {
+    Item(DropdownItem<T>),
+    Separator,
+    Group {
+        title: String,
+        entries: Vec<DropdownEntry<T>>,
+    },
+}
+impl<T> DropdownEntry<T> {
+    pub fn group(title: impl Into<String>, entries: Vec<DropdownEntry<T>>) -> Self {
+        Self::Group {
+            title: title.into(),
+            entries,
+        }
+    }
+}
+impl<T> From<DropdownItem<T>> for DropdownEntry<T> {
+    fn from(item: DropdownItem<T>) -> Self {
+        Self::Item(item)
+    }
+}
+
+#[derive(Clone)]
+enum RowKind<T> {
+    Header(String),
+    Separator,
+    Item(DropdownItem<T>),
+}
+
+/// A row of the flattened [`DropdownEntry`] tree.
+#[derive(Clone)]
+struct FlatRow<T> {
+    kind: RowKind<T>,
+    depth: usize,
+    /// Title of the innermost group containing the row
+    group: Option<String>,
+}
+impl<T> FlatRow<T> {
+    fn item(&self) -> Option<&DropdownItem<T>> {
+        match &self.kind {
+            RowKind::Item(item) => Some(item),
+            _ => None,
+        }
+    }
+}
+
+fn flatten<T: Clone>(
+    entries: &[DropdownEntry<T>],
+    depth: usize,
+    group: Option<&String>,
+    rows: &mut im::Vector<FlatRow<T>>,
+) {
+    for entry in entries {
+        let kind = match entry {
+            DropdownEntry::Item(item) => RowKind::Item(item.clone()),
+            DropdownEntry::Separator => RowKind::Separator,
+            DropdownEntry::Group { title, entries } => {
+                rows.push_back(FlatRow {
+                    kind: RowKind::Header(title.clone()),
+                    depth,
+                    group: Some(title.clone()),
+                });
+                flatten(entries, depth + 1, Some(title), rows);
+                continue;
+            }
+        };
+        rows.push_back(FlatRow {
+            kind,
+            depth,
+            group: group.cloned(),
+        });
+    }
+}
+
+/// A dropdown over a tree of [`DropdownEntry`]s. Group headers, separators and disabled items
+/// can't be selected and are skipped by the keyboard.
+pub fn grouped_dropdown<DS, T, K>(
+    entries: ReadSignal<Vec<DropdownEntry<T>>>,
+    key: impl Fn(&T) -> K + Copy + 'static,
+    selection: Selection<K>,
+    on_select: impl Fn(T) + Copy + 'static,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    T: Clone + 'static,
+    K: Clone + Hash + Eq + 'static,
+{
+    let rows = create_rw_signal(im::Vector::new());
+    create_effect(move |_| {
+        let mut flat = im::Vector::new();
+        entries.with(|entries| flatten(entries, 0, None, &mut flat));
+        rows.set(flat);
+    });
+
+    dropdown_core::<DS, _, _, _, _, _>(
+        rows.read_only(),
+        move |row: &FlatRow<T>| row.item().map(|item| key(&item.value)),
+        |row| {
+            row.item()
+                .map(|item| item.label.clone())
+                .unwrap_or_default()
+        },
+        |row| row.item().is_some_and(|item| !item.disabled),
+        |row| row.group.clone(),
+        selection,
+        empty,
+        |current| {
+            views::label(move || {
+                current()
+                    .and_then(|row| row.item().map(|item| item.label.clone()))
+                    .unwrap_or_default()
+            })
+        },
+        grouped_row::<DS, T>,
+        move |row| {
+            if let Some(item) = row.item() {
+                on_select(item.value.clone());
+            }
+        },
+        styles,
+    )
+}
+
+fn grouped_row<DS: DesignSystem + 'static, T: 'static>(row: FlatRow<T>) -> Box<dyn View> {
+    let indent = DS::PADDING * row.depth as f32;
+    match row.kind {
+        RowKind::Header(title) => Box::new(views::label(move || title.clone()).style(move |s| {
+            s.padding_left(indent)
+                .font_size(DS::BASE_FONT_SIZE * 0.85)
+                .ld_color(TextColor, DS::TEXT.base.set_alpha(70))
+        })),
+        RowKind::Separator => Box::new(empty().style(|s| {
+            s.width_full()
+                .height(DS::BORDER_SMALL)
+                .margin_vert(DS::PADDING / 2.)
+                .ld_color(Background, DS::BACKGROUND_3.base)
+        })),
+        RowKind::Item(item) => {
+            let icon_size = DS::BASE_FONT_SIZE;
+            let icon: Box<dyn View> = match item.icon {
+                Some(icon) => Box::new(icon().style(move |s| s.size(icon_size, icon_size))),
+                None => Box::new(empty()),
+            };
+            let label = item.label;
+            let font_family = item.font_family;
+            let secondary = item.secondary.unwrap_or_default();
+            let disabled = item.disabled;
+            Box::new(
+                h_stack((
+                    icon,
+                    views::label(move || label.clone()).style(move |s| {
+                        s.flex_grow(1.)
+                            .apply_opt(font_family.clone(), |s, family| s.font_family(family))
+                    }),
+                    views::label(move || secondary.clone()).style(|s| {
+                        s.font_size(DS::BASE_FONT_SIZE * 0.85)
+                            .ld_color(TextColor, DS::TEXT.base.set_alpha(60))
+                    }),
+                ))
+                .style(move |s| {
+                    s.width_full()
+                        .items_center()
+                        .gap(DS::PADDING / 2., 0.)
+                        .padding_left(indent)
+                        .apply_if(disabled, |s| {
+                            s.ld_color(TextColor, DS::TEXT.base.set_alpha(40))
+                        })
+                }),
+            )
+        }
+    }
+}
+
+/// The selected position, kept in sync with the key signal for [`Selection::Key`].
+fn selection_index<T: 'static, K: Clone + PartialEq + 'static>(
+    items: ReadSignal<im::Vector<T>>,
+    key: impl Fn(&T) -> Option<K> + Copy + 'static,
+    selection: Selection<K>,
+) -> RwSignal<Option<usize>> {
+    let keys = match selection {
+        Selection::Index(selected) => return selected,
+        Selection::Key(keys) => keys,
+    };
+    let selected = create_rw_signal(None);
+    // find the key again whenever it or the items change
+    create_effect(move |_| {
+        let found = keys.with(|wanted| {
+            let wanted = wanted.as_ref()?;
+            items.with(|list| {
+                list.iter()
+                    .position(|item| key(item).as_ref() == Some(wanted))
+            })
+        });
+        if selected.get_untracked() != found {
+            selected.set(found);
+        }
+    });
+    // and report picks from the list back as keys
+    create_effect(move |_| {
+        let picked = selected
+            .get()
+            .and_then(|idx| items.with_untracked(|list| list.get(idx).and_then(key)));
+        if picked.is_some() && keys.with_untracked(|current| current != &picked) {
+            keys.set(picked);
+        }
+    });
+    selected
+}
+
+pub(crate) fn arrow_and_container<DS: DesignSystem + 'static>(
+    selector: ArrowSelect,
+    styles: ReadSignal<DropDownStyles>,
+    on_click: impl Fn() + 'static,
+) -> impl View {
+    let arrow = match selector {
+        ArrowSelect::TopLeft => icon_chevron_up(),
+        ArrowSelect::BottomLeft | ArrowSelect::Right => icon_chevron_down(),
+    }
+    .style(move |s| {
+        DPStyles::button_style(s).dynamic_color(TextColor, styles.with(|val| val.arrow.clone()))
+    });
+    let arrow = container(arrow);
+    match selector {
+        // the steppers repeat while held
+        ArrowSelect::TopLeft | ArrowSelect::BottomLeft => {
+            arrow.on_press_repeat(DEFAULT_REPEAT_DELAY, DEFAULT_REPEAT_INTERVAL, on_click)
+        }
+        ArrowSelect::Right => arrow.on_click_stop(move |_| on_click()),
+    }
+    .style(move |s| {
+        DPStyles::button_cont_style::<DS>(s, selector)
+            .dynamic_color(Background, styles.with(|val| val.button_cont.clone()))
+    })
+}
+
+fn increment_and_select<T: Clone + 'static>(
+    step: isize,
+    selected: RwSignal<Option<usize>>,
+    items: ReadSignal<im::Vector<T>>,
+    enabled: impl Fn(usize) -> bool,
+    on_select: impl Fn(T) + 'static + Copy,
+) {
+    let len = items.with(|list| list.len());
+    let current = selected.get_untracked();
+    let new_val = next_enabled(current, len, step, false, enabled);
+    if new_val.is_some() && new_val != current {
+        selected.set(new_val);
+        if let Some(item) = new_val.and_then(|idx| items.with(|list| list.get(idx).cloned())) {
+            on_select(item);
+        }
+    }
+}
+
+/// The up and down chevrons of a stepper. `on_step` gets -1 for up and 1 for down, repeating
+/// while a button is held.
+pub(crate) fn stepper_buttons<DS: DesignSystem + 'static>(
+    styles: ReadSignal<DropDownStyles>,
+    on_step: impl Fn(isize) + Copy + 'static,
+) -> impl View {
+    v_stack((
+        arrow_and_container::<DS>(ArrowSelect::TopLeft, styles, move || on_step(-1)),
+        arrow_and_container::<DS>(ArrowSelect::BottomLeft, styles, move || on_step(1)),
+    ))
+    .style(|s| s.items_center().height_full())
+}
+
+pub struct DPStyles {}
+impl DPStyles {
+    const ICW: f64 = 15.;
+    pub const SCROLL_CONTAINER_PADDING: f64 = 6.;
+
+    fn button_style(s: Style) -> Style {
+        s.size(Self::ICW, Self::ICW)
+    }
+
+    fn button_cont_style<DS: DesignSystem>(s: Style, selector: ArrowSelect) -> Style {
+        let horiz_padding = DS::PADDING / 2.;
+        s.flex_grow(1.)
+            .items_center()
+            .justify_center()
+            .padding_horiz(horiz_padding)
+            .padding_top(match selector {
+                ArrowSelect::TopLeft => 1.,
+                _ => 0.,
+            })
+            .padding_bottom(match selector {
+                ArrowSelect::BottomLeft => 1.,
+                _ => 0.,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measured_rows_move_the_rows_below_them() {
+        let metrics = RowMetrics::new(RowHeight::Measured { estimate: 10. });
+        assert_eq!(metrics.top_of(3), 30.);
+        assert_eq!(metrics.index_at(25., 5), 2);
+
+        metrics.measure(1, 30.);
+        assert_eq!(metrics.top_of(1), 10.);
+        assert_eq!(metrics.top_of(3), 50.);
+        assert_eq!(metrics.index_at(39.9, 5), 1);
+        assert_eq!(metrics.index_at(40., 5), 2);
+        assert_eq!(metrics.index_at(1000., 5), 4);
+
+        metrics.clear();
+        assert_eq!(metrics.top_of(3), 30.);
+    }
+
+    #[test]
+    fn fixed_rows_need_no_measuring() {
+        let metrics = RowMetrics::new(RowHeight::Fixed(20.));
+        metrics.measure(0, 50.);
+        assert_eq!(metrics.top_of(2), 40.);
+        assert_eq!(metrics.index_at(59., 10), 2);
+        assert_eq!(metrics.index_at(0., 0), 0);
+    }
+}
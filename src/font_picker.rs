@@ -0,0 +1,223 @@
+use floem::{
+    cosmic_text::FONT_SYSTEM,
+    reactive::{create_effect, create_rw_signal, ReadSignal, RwSignal},
+    style::TextColor,
+    view::View,
+    views::{container, h_stack, Decorators},
+};
+
+use crate::{
+    combobox::{combobox_with, ComboValue, ComboboxOptions, OptionSource},
+    dropdown::{grouped_dropdown, DropDownStyles, DropdownEntry, DropdownItem, Selection},
+    icons::icon_star,
+    style::{DesignSystem, ExtDynamicColor},
+};
+
+pub const MAX_RECENT_FONTS: usize = 8;
+const FAVORITES: &str = "Favorites";
+const RECENT: &str = "Recent";
+const ALL_FONTS: &str = "All fonts";
+pub const PRESET_FONT_SIZES: [f32; 16] = [
+    8., 9., 10., 11., 12., 14., 16., 18., 20., 24., 28., 32., 36., 48., 60., 72.,
+];
+
+/// The fonts a user starred or picked lately. Kept outside the picker so it can be shared
+/// between pickers and persisted.
+#[derive(Clone, Copy)]
+pub struct FontHistory {
+    pub favorites: RwSignal<im::Vector<String>>,
+    /// Most recent first, at most [`MAX_RECENT_FONTS`]
+    pub recents: RwSignal<im::Vector<String>>,
+}
+impl Default for FontHistory {
+    fn default() -> Self {
+        Self {
+            favorites: create_rw_signal(im::Vector::new()),
+            recents: create_rw_signal(im::Vector::new()),
+        }
+    }
+}
+impl FontHistory {
+    pub fn toggle_favorite(&self, family: &str) {
+        self.favorites.update(
+            |favorites| match favorites.iter().position(|fav| fav == family) {
+                Some(idx) => {
+                    favorites.remove(idx);
+                }
+                None => favorites.push_back(family.to_string()),
+            },
+        );
+    }
+
+    pub fn push_recent(&self, family: String) {
+        self.recents.update(|recents| {
+            recents.retain(|recent| recent != &family);
+            recents.push_front(family);
+            recents.truncate(MAX_RECENT_FONTS);
+        });
+    }
+}
+
+/// Family names of the installed fonts, sorted and without duplicates.
+pub fn system_font_families() -> Vec<String> {
+    let font_system = FONT_SYSTEM.lock();
+    let mut families: Vec<String> = font_system
+        .db()
+        .faces()
+        .filter_map(|face| face.families.first().map(|(name, _)| name.clone()))
+        .collect();
+    families.sort_by_cached_key(|name| name.to_lowercase());
+    families.dedup();
+    families
+}
+
+fn font_group<'a>(
+    title: &'static str,
+    families: impl Iterator<Item = &'a String>,
+) -> DropdownEntry<(&'static str, String)> {
+    let items = families
+        .map(|name| {
+            DropdownItem::new((title, name.clone()), name.clone())
+                .font_family(name)
+                .into()
+        })
+        .collect();
+    DropdownEntry::group(title, items)
+}
+
+/// A dropdown of the installed font families, each drawn in its own face, with the favorites
+/// and recent picks of `history` listed first. The star next to it toggles the current family
+/// as a favorite.
+pub fn font_picker<DS: DesignSystem + 'static>(
+    family: RwSignal<String>,
+    history: FontHistory,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View {
+    let all = system_font_families();
+    // a family can be listed in several groups, so rows are told apart by their group as well
+    let entries = create_rw_signal(Vec::new());
+    create_effect(move |_| {
+        let mut list = Vec::new();
+        let favorites = history.favorites.get();
+        if !favorites.is_empty() {
+            list.push(font_group(FAVORITES, favorites.iter()));
+        }
+        let recents = history.recents.get();
+        if !recents.is_empty() {
+            list.push(font_group(RECENT, recents.iter()));
+        }
+        list.push(font_group(ALL_FONTS, all.iter()));
+        entries.set(list);
+    });
+
+    // keep the row that was picked, otherwise show the family in the first group listing it
+    let selected: RwSignal<Option<(&'static str, String)>> = create_rw_signal(None);
+    create_effect(move |_| {
+        let family = family.get();
+        let listed_in = |group: &str| match group {
+            FAVORITES => history
+                .favorites
+                .with(|favorites| favorites.contains(&family)),
+            RECENT => history.recents.with(|recents| recents.contains(&family)),
+            _ => true,
+        };
+        let kept = selected.with_untracked(|selected| {
+            selected
+                .as_ref()
+                .is_some_and(|(group, picked)| picked == &family && listed_in(group))
+        });
+        if !kept {
+            let group = [FAVORITES, RECENT]
+                .into_iter()
+                .find(|group| listed_in(group))
+                .unwrap_or(ALL_FONTS);
+            selected.set(Some((group, family)));
+        }
+    });
+
+    let is_favorite = move || {
+        family.with(|family| {
+            history
+                .favorites
+                .with(|favorites| favorites.contains(family))
+        })
+    };
+    let star = container(icon_star().style(move |s| {
+        let size = DS::BASE_FONT_SIZE;
+        s.size(size, size)
+            .ld_color(TextColor, DS::TEXT.base.set_alpha(40))
+            .apply_if(is_favorite(), |s| {
+                s.ld_color(TextColor, styles.with(|val| val.sel_cont.base))
+            })
+    }))
+    .on_click_stop(move |_| family.with_untracked(|family| history.toggle_favorite(family)))
+    .style(|s| {
+        s.padding_horiz(DS::PADDING / 2.)
+            .items_center()
+            .height_full()
+    });
+
+    h_stack((
+        container(grouped_dropdown::<DS, _, _>(
+            entries.read_only(),
+            |value: &(&'static str, String)| value.clone(),
+            Selection::Key(selected),
+            move |(_, picked)| {
+                family.set(picked.clone());
+                history.push_recent(picked);
+            },
+            styles,
+        ))
+        .style(|s| s.flex_grow(1.).height_full()),
+        star,
+    ))
+    .style(|s| s.items_center().size_full())
+}
+
+fn format_size(size: f32) -> String {
+    format!("{size}")
+}
+
+/// A combobox of [`PRESET_FONT_SIZES`] that also takes any typed size, with or without "pt".
+/// Text that isn't a positive number is discarded.
+pub fn font_size_picker<DS: DesignSystem + 'static>(
+    size: RwSignal<f32>,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View {
+    let presets = create_rw_signal(PRESET_FONT_SIZES.into_iter().collect::<im::Vector<_>>());
+    let query = create_rw_signal(String::new());
+    let committed = create_rw_signal(String::new());
+    create_effect(move |_| {
+        let text = format_size(size.get());
+        committed.set(text.clone());
+        query.set(text);
+    });
+
+    combobox_with::<DS, f32>(
+        presets.read_only(),
+        query,
+        committed,
+        OptionSource::Local,
+        |size| format_size(*size),
+        move |value| match value {
+            ComboValue::Item(new) => size.set(new),
+            ComboValue::Text(text) => {
+                let parsed = text.trim().trim_end_matches("pt").trim().parse::<f32>();
+                match parsed {
+                    Ok(new) if new.is_finite() && new > 0. => size.set(new),
+                    // put the current size back
+                    _ => {
+                        let text = format_size(size.get_untracked());
+                        committed.set(text.clone());
+                        query.set(text);
+                    }
+                }
+            }
+        },
+        ComboboxOptions {
+            allow_free_text: true,
+            ..Default::default()
+        },
+        styles,
+    )
+}
@@ -0,0 +1,228 @@
+/// Where and how well a pattern matched a candidate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char positions in the candidate that matched the pattern, ascending
+    pub indices: Vec<usize>,
+}
+
+const MATCH: i64 = 16;
+const CONSECUTIVE: i64 = 12;
+const WORD_START: i64 = 10;
+const FIRST_CHAR: i64 = 8;
+const GAP: i64 = 2;
+const MAX_GAP_PENALTY: i64 = 12;
+
+fn is_word_start(chars: &[char], idx: usize) -> bool {
+    let Some(&prev) = idx.checked_sub(1).and_then(|prev| chars.get(prev)) else {
+        return true;
+    };
+    let current = chars[idx];
+    !prev.is_alphanumeric() || (prev.is_lowercase() && current.is_uppercase())
+}
+
+/// Match `pattern` as a case insensitive subsequence of `candidate`. Consecutive matches and
+/// matches at the start of words score higher, gaps between matched characters lower. An empty
+/// pattern matches everything with a score of zero.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            indices: Vec::new(),
+        });
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    // lowercased the same way as the pattern, where a char can turn into several ('İ' into
+    // 'i' and a combining dot), along with the position of the char each came from
+    let (lower, origin): (Vec<char>, Vec<usize>) = chars
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, c)| c.to_lowercase().map(move |lower| (lower, idx)))
+        .unzip();
+
+    // find the first place the whole pattern fits, then walk back from its end to the
+    // latest possible start so the match is as tight as possible
+    let mut next = 0;
+    let mut end = None;
+    for (idx, c) in lower.iter().enumerate() {
+        if *c == pattern[next] {
+            next += 1;
+            if next == pattern.len() {
+                end = Some(idx);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut remaining = pattern.len();
+    let mut start = end;
+    for idx in (0..=end).rev() {
+        if lower[idx] == pattern[remaining - 1] {
+            remaining -= 1;
+            if remaining == 0 {
+                start = idx;
+                break;
+            }
+        }
+    }
+
+    let mut indices = Vec::with_capacity(pattern.len());
+    let mut next = 0;
+    for (idx, c) in lower.iter().enumerate().take(end + 1).skip(start) {
+        if next < pattern.len() && *c == pattern[next] {
+            if indices.last() != Some(&origin[idx]) {
+                indices.push(origin[idx]);
+            }
+            next += 1;
+        }
+    }
+
+    let mut score = 0;
+    let mut prev: Option<usize> = None;
+    for &idx in &indices {
+        score += MATCH;
+        if is_word_start(&chars, idx) {
+            score += WORD_START;
+        }
+        match prev {
+            Some(prev) if idx == prev + 1 => score += CONSECUTIVE,
+            Some(prev) => score -= ((idx - prev - 1) as i64 * GAP).min(MAX_GAP_PENALTY),
+            None if idx == 0 => score += FIRST_CHAR,
+            None => score -= (idx as i64).min(MAX_GAP_PENALTY),
+        }
+        prev = Some(idx);
+    }
+    Some(FuzzyMatch { score, indices })
+}
+
+/// Match every item against `pattern` and return the positions of the ones that matched, best
+/// first. Items with equal scores keep their original order.
+pub fn fuzzy_filter<T>(
+    items: impl IntoIterator<Item = T>,
+    pattern: &str,
+    label: impl Fn(&T) -> String,
+) -> Vec<(usize, FuzzyMatch)> {
+    let mut matches: Vec<(usize, FuzzyMatch)> = items
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, item)| fuzzy_match(pattern, &label(&item)).map(|found| (idx, found)))
+        .collect();
+    matches.sort_by_key(|(_, found)| std::cmp::Reverse(found.score));
+    matches
+}
+
+/// Split `text` into runs of matched and unmatched characters for highlighting.
+pub fn highlight_segments(text: &str, indices: &[usize]) -> Vec<(String, bool)> {
+    let mut segments: Vec<(String, bool)> = Vec::new();
+    let mut matched = indices.iter().peekable();
+    for (idx, c) in text.chars().enumerate() {
+        let is_match = matched.next_if_eq(&&idx).is_some();
+        match segments.last_mut() {
+            Some((segment, last)) if *last == is_match => segment.push(c),
+            _ => segments.push((c.to_string(), is_match)),
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indices(pattern: &str, candidate: &str) -> Option<Vec<usize>> {
+        fuzzy_match(pattern, candidate).map(|found| found.indices)
+    }
+
+    fn score(pattern: &str, candidate: &str) -> i64 {
+        fuzzy_match(pattern, candidate).unwrap().score
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything() {
+        let found = fuzzy_match(" ", "anything").unwrap();
+        assert_eq!(found.score, 0);
+        assert!(found.indices.is_empty());
+    }
+
+    #[test]
+    fn matches_case_insensitive_subsequence() {
+        assert_eq!(indices("fb", "FooBar"), Some(vec![0, 3]));
+        assert_eq!(indices("FOO BAR", "foobar"), Some(vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(indices("ba", "ab"), None);
+        assert_eq!(indices("xyz", "FooBar"), None);
+    }
+
+    #[test]
+    fn prefers_the_tightest_match() {
+        assert_eq!(indices("abc", "a_xabc"), Some(vec![3, 4, 5]));
+    }
+
+    #[test]
+    fn indices_count_chars_not_bytes() {
+        assert_eq!(indices("ée", "café crème"), Some(vec![3, 9]));
+    }
+
+    #[test]
+    fn lowercases_candidates_like_patterns() {
+        assert_eq!(indices("İs", "İstanbul"), Some(vec![0, 1]));
+        assert_eq!(indices("i̇s", "İSTANBUL"), Some(vec![0, 1]));
+        assert_eq!(indices("is", "İstanbul"), Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn scores_consecutive_and_word_starts_higher() {
+        assert!(score("ab", "abx") > score("ab", "axb"));
+        assert!(score("b", "foo_bar") > score("b", "foobar"));
+        assert!(score("b", "fooBar") > score("b", "foobar"));
+        assert!(score("f", "foo") > score("f", "xfoo"));
+    }
+
+    #[test]
+    fn filter_sorts_best_first_and_keeps_ties_in_order() {
+        let items = ["axb", "ab", "xab", "nothing"];
+        let found: Vec<usize> = fuzzy_filter(items, "ab", |item| item.to_string())
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(found, vec![1, 0, 2]);
+
+        let ties: Vec<usize> = fuzzy_filter(["ab", "ab"], "ab", |item| item.to_string())
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(ties, vec![0, 1]);
+    }
+
+    #[test]
+    fn segments_split_matched_runs() {
+        let owned = |parts: &[(&str, bool)]| -> Vec<(String, bool)> {
+            parts
+                .iter()
+                .map(|(text, matched)| (text.to_string(), *matched))
+                .collect()
+        };
+
+        assert_eq!(
+            highlight_segments("FooBar", &[0, 3]),
+            owned(&[("F", true), ("oo", false), ("B", true), ("ar", false)])
+        );
+        assert_eq!(
+            highlight_segments("FooBar", &[0, 1, 2]),
+            owned(&[("Foo", true), ("Bar", false)])
+        );
+        assert_eq!(
+            highlight_segments("FooBar", &[]),
+            owned(&[("FooBar", false)])
+        );
+        assert_eq!(
+            highlight_segments("café", &[3]),
+            owned(&[("caf", false), ("é", true)])
+        );
+        assert!(highlight_segments("", &[]).is_empty());
+    }
+}
@@ -0,0 +1,562 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use floem::{
+    event::{Event, EventListener},
+    id::Id,
+    kurbo::{Point, Vec2},
+    pointer::PointerButton,
+    view::View,
+    views::Decorators,
+    EventPropagation,
+};
+
+use crate::handlers::{now, schedule_at};
+
+/// How far the pointer may travel, in pixels, before a press stops counting as a tap.
+pub const DEFAULT_SLOP: f64 = 6.;
+pub const DEFAULT_DOUBLE_TAP: Duration = Duration::from_millis(300);
+pub const DEFAULT_LONG_PRESS: Duration = Duration::from_millis(500);
+pub const DEFAULT_REPEAT_DELAY: Duration = Duration::from_millis(400);
+pub const DEFAULT_REPEAT_INTERVAL: Duration = Duration::from_millis(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanPhase {
+    Start,
+    Update,
+    End,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PanEvent {
+    pub phase: PanPhase,
+    /// Where the pointer went down
+    pub origin: Point,
+    pub pos: Point,
+    /// Movement since the previous pan event
+    pub delta: Vec2,
+    /// Movement since `origin`
+    pub translation: Vec2,
+    /// Pixels per second. Each new sample is averaged with the previous velocity, so older
+    /// samples fade out exponentially.
+    pub velocity: Vec2,
+}
+
+/// The pointer input gesture recognizers work on, in the coordinates of their view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PointerInput {
+    /// A primary button press
+    Down(Point),
+    Move(Point),
+    Up(Point),
+    Leave,
+}
+
+/// A cancellable timer: starting a new one or calling `cancel` invalidates the previous one.
+/// Runs on the [`VirtualClock`](crate::handlers::VirtualClock) when one is running.
+#[derive(Clone, Default)]
+pub(crate) struct TimerSlot(Rc<Cell<u64>>);
+impl TimerSlot {
+    pub(crate) fn start(&self, after: Duration, action: impl FnOnce() + 'static) {
+        let generation = self.0.get() + 1;
+        self.0.set(generation);
+        let slot = self.0.clone();
+        schedule_at(now() + after, move || {
+            if slot.get() == generation {
+                action();
+            }
+        });
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+fn repeat(slot: TimerSlot, after: Duration, interval: Duration, action: Rc<dyn Fn()>) {
+    let next = slot.clone();
+    slot.start(after, move || {
+        action();
+        repeat(next, interval, interval, action);
+    });
+}
+
+/// Whether a recognizer has taken over the current press. Shared by the recognizers of a view
+/// and reset on every press, so a press that became a long press or a pan isn't also a tap.
+#[derive(Clone, Default)]
+struct PressClaim(Rc<Cell<bool>>);
+impl PressClaim {
+    /// Take the press. Returns false if another recognizer already has it.
+    fn claim(&self) -> bool {
+        !self.0.replace(true)
+    }
+
+    fn is_claimed(&self) -> bool {
+        self.0.get()
+    }
+}
+
+/// Returns whether it used the input.
+type Recognizer = Box<dyn FnMut(PointerInput) -> bool>;
+
+/// The recognizers attached to one view.
+#[derive(Default)]
+struct GestureSet {
+    claim: PressClaim,
+    recognizers: Vec<Recognizer>,
+}
+impl GestureSet {
+    fn input(&mut self, input: PointerInput) -> bool {
+        if let PointerInput::Down(_) = input {
+            self.claim.0.set(false);
+        }
+        let mut used = false;
+        for recognizer in &mut self.recognizers {
+            used |= recognizer(input);
+        }
+        used
+    }
+}
+
+thread_local! {
+    static GESTURES: RefCell<HashMap<Id, Rc<RefCell<GestureSet>>>> = RefCell::new(HashMap::new());
+}
+
+/// Add a recognizer to the gesture set of `view`, creating the set and its event handlers for
+/// the first one.
+fn attach<V: View + Decorators>(
+    view: V,
+    recognizer: impl FnOnce(PressClaim, Id) -> Recognizer,
+) -> V {
+    let id = view.id();
+    let existing = GESTURES.with(|gestures| gestures.borrow().get(&id).cloned());
+    let set = existing.clone().unwrap_or_default();
+    let claim = set.borrow().claim.clone();
+    set.borrow_mut().recognizers.push(recognizer(claim, id));
+    if existing.is_some() {
+        return view;
+    }
+    GESTURES.with(|gestures| gestures.borrow_mut().insert(id, set.clone()));
+
+    let input = Rc::new(move |input: Option<PointerInput>| match input {
+        Some(input) if set.borrow_mut().input(input) => EventPropagation::Stop,
+        _ => EventPropagation::Continue,
+    });
+    let (down, moved, up, leave) = (input.clone(), input.clone(), input.clone(), input);
+    view.on_event(EventListener::PointerDown, move |event| {
+        down(match event {
+            Event::PointerDown(pointer) if matches!(pointer.button, PointerButton::Primary) => {
+                Some(PointerInput::Down(pointer.pos))
+            }
+            _ => None,
+        })
+    })
+    .on_event(EventListener::PointerMove, move |event| {
+        moved(event.point().map(PointerInput::Move))
+    })
+    .on_event(EventListener::PointerUp, move |event| {
+        up(event.point().map(PointerInput::Up))
+    })
+    .on_event(EventListener::PointerLeave, move |_| {
+        leave(Some(PointerInput::Leave))
+    })
+    .on_cleanup(move || {
+        GESTURES.with(|gestures| gestures.borrow_mut().remove(&id));
+    })
+}
+
+fn tap(claim: PressClaim, action: impl Fn(Point) + 'static) -> Recognizer {
+    let mut down_at: Option<Point> = None;
+    Box::new(move |input| match input {
+        PointerInput::Down(pos) => {
+            down_at = Some(pos);
+            false
+        }
+        PointerInput::Move(pos) => {
+            if down_at.is_some_and(|origin| origin.distance(pos) > DEFAULT_SLOP) {
+                down_at = None;
+            }
+            false
+        }
+        PointerInput::Up(pos) => match down_at.take() {
+            Some(_) if !claim.is_claimed() => {
+                action(pos);
+                true
+            }
+            _ => false,
+        },
+        PointerInput::Leave => {
+            down_at = None;
+            false
+        }
+    })
+}
+
+fn double_tap(
+    claim: PressClaim,
+    interval: Duration,
+    action: impl Fn(Point) + 'static,
+) -> Recognizer {
+    let last_tap: Cell<Option<(Instant, Point)>> = Cell::new(None);
+    tap(claim, move |pos| {
+        let now = now();
+        match last_tap.take() {
+            Some((at, first))
+                if now.duration_since(at) <= interval && first.distance(pos) <= DEFAULT_SLOP =>
+            {
+                action(pos)
+            }
+            _ => last_tap.set(Some((now, pos))),
+        }
+    })
+}
+
+fn long_press(
+    claim: PressClaim,
+    timeout: Duration,
+    action: impl Fn(Point) + 'static,
+) -> Recognizer {
+    let action = Rc::new(action);
+    let timer = TimerSlot::default();
+    let mut down_at: Option<Point> = None;
+    Box::new(move |input| {
+        match input {
+            PointerInput::Down(pos) => {
+                down_at = Some(pos);
+                let (action, claim) = (action.clone(), claim.clone());
+                timer.start(timeout, move || {
+                    if claim.claim() {
+                        action(pos);
+                    }
+                });
+            }
+            PointerInput::Move(pos) => {
+                if down_at.is_some_and(|origin| origin.distance(pos) > DEFAULT_SLOP) {
+                    down_at = None;
+                    timer.cancel();
+                }
+            }
+            PointerInput::Up(_) | PointerInput::Leave => {
+                down_at = None;
+                timer.cancel();
+            }
+        }
+        false
+    })
+}
+
+fn pan(claim: PressClaim, id: Id, slop: f64, action: impl Fn(PanEvent) + 'static) -> Recognizer {
+    let mut origin: Option<Point> = None;
+    let mut panning = false;
+    let mut last: Option<(Instant, Point)> = None;
+    let mut velocity = Vec2::ZERO;
+    Box::new(move |input| match input {
+        PointerInput::Down(pos) => {
+            origin = Some(pos);
+            panning = false;
+            last = Some((now(), pos));
+            velocity = Vec2::ZERO;
+            false
+        }
+        PointerInput::Move(pos) => {
+            let Some(start) = origin else {
+                return false;
+            };
+            let phase = if panning {
+                PanPhase::Update
+            } else if start.distance(pos) > slop && claim.claim() {
+                panning = true;
+                // keep receiving moves and the release when the pointer leaves the view
+                id.request_active();
+                PanPhase::Start
+            } else {
+                return false;
+            };
+            let now = now();
+            let (last_at, last_pos) = last.unwrap_or((now, start));
+            let elapsed = now.duration_since(last_at).as_secs_f64();
+            if elapsed > 0. {
+                let sample = (pos - last_pos) / elapsed;
+                velocity = (velocity + sample) / 2.;
+            }
+            last = Some((now, pos));
+            action(PanEvent {
+                phase,
+                origin: start,
+                pos,
+                delta: pos - last_pos,
+                translation: pos - start,
+                velocity,
+            });
+            true
+        }
+        PointerInput::Up(pos) => {
+            let (Some(start), true) = (origin.take(), std::mem::take(&mut panning)) else {
+                return false;
+            };
+            let last_pos = last.take().map(|(_, last)| last).unwrap_or(start);
+            action(PanEvent {
+                phase: PanPhase::End,
+                origin: start,
+                pos,
+                delta: pos - last_pos,
+                translation: pos - start,
+                velocity,
+            });
+            true
+        }
+        // the view is active while panning, so the pan goes on until the release
+        PointerInput::Leave => false,
+    })
+}
+
+fn press_repeat(
+    claim: PressClaim,
+    delay: Duration,
+    interval: Duration,
+    action: impl Fn() + 'static,
+) -> Recognizer {
+    let action: Rc<dyn Fn()> = Rc::new(action);
+    let timer = TimerSlot::default();
+    Box::new(move |input| match input {
+        PointerInput::Down(_) => {
+            claim.claim();
+            action();
+            repeat(timer.clone(), delay, interval, action.clone());
+            true
+        }
+        PointerInput::Up(_) | PointerInput::Leave => {
+            timer.cancel();
+            false
+        }
+        PointerInput::Move(_) => false,
+    })
+}
+
+/// Gesture recognizers for any view. The recognizers of one view share each press: once a long
+/// press has fired or a pan has started, the press doesn't count as a tap anymore.
+pub trait ExtGesture {
+    /// A primary press and release without moving further than `DEFAULT_SLOP`.
+    fn on_tap(self, action: impl Fn(Point) + 'static) -> Self;
+
+    /// Two taps within `interval` of each other and within `DEFAULT_SLOP` of the first.
+    fn on_double_tap(self, interval: Duration, action: impl Fn(Point) + 'static) -> Self;
+
+    /// A press held in place for `timeout`. Fires while the pointer is still down.
+    fn on_long_press(self, timeout: Duration, action: impl Fn(Point) + 'static) -> Self;
+
+    /// A drag that starts once the pointer has moved further than `slop` from where it went down.
+    /// The drag keeps going outside the view until the pointer is released.
+    fn on_pan(self, slop: f64, action: impl Fn(PanEvent) + 'static) -> Self;
+
+    /// Fires once on press, then again every `interval` after an initial `delay` until release.
+    /// Meant for stepper and spinner buttons.
+    fn on_press_repeat(
+        self,
+        delay: Duration,
+        interval: Duration,
+        action: impl Fn() + 'static,
+    ) -> Self;
+}
+impl<T: View + Decorators> ExtGesture for T {
+    fn on_tap(self, action: impl Fn(Point) + 'static) -> Self {
+        attach(self, |claim, _| tap(claim, action))
+    }
+
+    fn on_double_tap(self, interval: Duration, action: impl Fn(Point) + 'static) -> Self {
+        attach(self, |claim, _| double_tap(claim, interval, action))
+    }
+
+    fn on_long_press(self, timeout: Duration, action: impl Fn(Point) + 'static) -> Self {
+        attach(self, |claim, _| long_press(claim, timeout, action))
+    }
+
+    fn on_pan(self, slop: f64, action: impl Fn(PanEvent) + 'static) -> Self {
+        attach(self, |claim, id| pan(claim, id, slop, action))
+    }
+
+    fn on_press_repeat(
+        self,
+        delay: Duration,
+        interval: Duration,
+        action: impl Fn() + 'static,
+    ) -> Self {
+        attach(self, |claim, _| {
+            press_repeat(claim, delay, interval, action)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::VirtualClock;
+
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn point(x: f64, y: f64) -> Point {
+        Point::new(x, y)
+    }
+
+    /// A gesture set with one recognizer built by `build`, as `attach` would set it up.
+    fn set(build: impl FnOnce(PressClaim, Id) -> Recognizer) -> GestureSet {
+        let mut set = GestureSet::default();
+        let recognizer = build(set.claim.clone(), Id::next());
+        set.recognizers.push(recognizer);
+        set
+    }
+
+    fn recorder<T: 'static>() -> (Rc<RefCell<Vec<T>>>, impl Fn(T) + 'static) {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let inner = calls.clone();
+        (calls, move |value| inner.borrow_mut().push(value))
+    }
+
+    #[test]
+    fn tap_fires_on_release_within_slop() {
+        let (taps, action) = recorder();
+        let mut gestures = set(|claim, _| tap(claim, action));
+
+        gestures.input(PointerInput::Down(point(10., 10.)));
+        gestures.input(PointerInput::Move(point(13., 10.)));
+        assert!(gestures.input(PointerInput::Up(point(13., 10.))));
+        assert_eq!(*taps.borrow(), vec![point(13., 10.)]);
+
+        // moving too far turns the press into something else
+        gestures.input(PointerInput::Down(point(10., 10.)));
+        gestures.input(PointerInput::Move(point(30., 10.)));
+        assert!(!gestures.input(PointerInput::Up(point(30., 10.))));
+        // and so does leaving the view
+        gestures.input(PointerInput::Down(point(10., 10.)));
+        gestures.input(PointerInput::Leave);
+        assert!(!gestures.input(PointerInput::Up(point(10., 10.))));
+        assert_eq!(taps.borrow().len(), 1);
+    }
+
+    #[test]
+    fn double_tap_needs_two_quick_taps_in_place() {
+        let (double_taps, action) = recorder();
+        let mut gestures = set(|claim, _| double_tap(claim, DEFAULT_DOUBLE_TAP, action));
+        let clock = VirtualClock::start(Instant::now());
+        let start = clock.now();
+        let tap_at = |gestures: &mut GestureSet, pos: Point| {
+            gestures.input(PointerInput::Down(pos));
+            gestures.input(PointerInput::Up(pos));
+        };
+
+        tap_at(&mut gestures, point(10., 10.));
+        clock.advance_to(start + 200 * MS);
+        tap_at(&mut gestures, point(12., 10.));
+        assert_eq!(*double_taps.borrow(), vec![point(12., 10.)]);
+
+        // too slow
+        clock.advance_to(start + 1000 * MS);
+        tap_at(&mut gestures, point(10., 10.));
+        clock.advance_to(start + 1301 * MS);
+        tap_at(&mut gestures, point(10., 10.));
+        // too far apart
+        clock.advance_to(start + 1400 * MS);
+        tap_at(&mut gestures, point(40., 10.));
+        assert_eq!(double_taps.borrow().len(), 1);
+    }
+
+    #[test]
+    fn long_press_fires_after_timeout_and_suppresses_tap() {
+        let (presses, on_press) = recorder();
+        let (taps, on_tap) = recorder();
+        let mut gestures = set(|claim, _| long_press(claim, DEFAULT_LONG_PRESS, on_press));
+        let claim = gestures.claim.clone();
+        gestures.recognizers.push(tap(claim, on_tap));
+        let clock = VirtualClock::start(Instant::now());
+        let start = clock.now();
+
+        gestures.input(PointerInput::Down(point(10., 10.)));
+        clock.advance_to(start + 499 * MS);
+        assert!(presses.borrow().is_empty());
+        clock.advance_to(start + 500 * MS);
+        assert_eq!(*presses.borrow(), vec![point(10., 10.)]);
+        gestures.input(PointerInput::Up(point(10., 10.)));
+        assert!(taps.borrow().is_empty());
+
+        // a short press is a tap and no long press
+        gestures.input(PointerInput::Down(point(10., 10.)));
+        clock.advance_to(start + 700 * MS);
+        gestures.input(PointerInput::Up(point(10., 10.)));
+        clock.run_pending();
+        assert_eq!(presses.borrow().len(), 1);
+        assert_eq!(taps.borrow().len(), 1);
+    }
+
+    #[test]
+    fn long_press_is_cancelled_by_moving() {
+        let (presses, action) = recorder();
+        let mut gestures = set(|claim, _| long_press(claim, DEFAULT_LONG_PRESS, action));
+        let clock = VirtualClock::start(Instant::now());
+
+        gestures.input(PointerInput::Down(point(10., 10.)));
+        gestures.input(PointerInput::Move(point(10., 30.)));
+        clock.run_pending();
+        assert!(presses.borrow().is_empty());
+    }
+
+    #[test]
+    fn pan_starts_past_slop_and_ends_on_release() {
+        let (pans, action) = recorder::<PanEvent>();
+        let (taps, on_tap) = recorder();
+        let mut gestures = set(|claim, id| pan(claim, id, DEFAULT_SLOP, action));
+        let claim = gestures.claim.clone();
+        gestures.recognizers.push(tap(claim, on_tap));
+        let clock = VirtualClock::start(Instant::now());
+        let start = clock.now();
+
+        gestures.input(PointerInput::Down(point(10., 10.)));
+        clock.advance_to(start + 10 * MS);
+        assert!(!gestures.input(PointerInput::Move(point(14., 10.))));
+        assert!(pans.borrow().is_empty());
+
+        clock.advance_to(start + 20 * MS);
+        assert!(gestures.input(PointerInput::Move(point(20., 10.))));
+        // leaving the view doesn't end the pan
+        gestures.input(PointerInput::Leave);
+        clock.advance_to(start + 30 * MS);
+        gestures.input(PointerInput::Move(point(-20., 10.)));
+        gestures.input(PointerInput::Up(point(-25., 10.)));
+
+        let pans = pans.borrow();
+        let phases: Vec<PanPhase> = pans.iter().map(|pan| pan.phase).collect();
+        assert_eq!(phases, [PanPhase::Start, PanPhase::Update, PanPhase::End]);
+        assert_eq!(pans[0].translation, Vec2::new(10., 0.));
+        assert_eq!(pans[1].delta, Vec2::new(-40., 0.));
+        assert_eq!(pans[2].translation, Vec2::new(-35., 0.));
+        assert!(pans[2].velocity.x < 0.);
+        assert!(taps.borrow().is_empty());
+    }
+
+    #[test]
+    fn press_repeat_fires_until_release() {
+        let count = Rc::new(Cell::new(0));
+        let inner = count.clone();
+        let mut gestures = set(|claim, _| {
+            press_repeat(claim, 400 * MS, 60 * MS, move || inner.set(inner.get() + 1))
+        });
+        let clock = VirtualClock::start(Instant::now());
+        let start = clock.now();
+
+        assert!(gestures.input(PointerInput::Down(point(0., 0.))));
+        assert_eq!(count.get(), 1);
+        clock.advance_to(start + 399 * MS);
+        assert_eq!(count.get(), 1);
+        clock.advance_to(start + 400 * MS);
+        assert_eq!(count.get(), 2);
+        clock.advance_to(start + 520 * MS);
+        assert_eq!(count.get(), 4);
+        gestures.input(PointerInput::Up(point(0., 0.)));
+        clock.advance_to(start + 2000 * MS);
+        assert_eq!(count.get(), 4);
+    }
+}
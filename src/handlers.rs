@@ -0,0 +1,475 @@
+//! Rate limiting combinators for event handlers.
+//!
+//! Each combinator wraps a handler and returns a handler with the same signature, so the result
+//! can be passed straight to [`EventHandlers`](crate::style::EventHandlers) (which expects
+//! `Fn(&Event) -> bool`), to [`ExtAnyEvent`](crate::style::ExtAnyEvent) or to
+//! `Decorators::on_event` (which expect `Fn(&Event) -> EventPropagation`). Delayed calls are
+//! scheduled with floem timers.
+//!
+//! The timing decisions live in [`Debouncer`] and [`Throttler`], which take the current instant as
+//! an argument so they can be driven without a running event loop. The combinators themselves can
+//! be run on a [`VirtualClock`], which is how recorded sessions are replayed at their original pace.
+
+use std::{
+    cell::{Cell, RefCell},
+    marker::PhantomData,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use floem::{action::exec_after, event::Event, EventPropagation};
+
+/// The value a wrapped handler returns for events it swallowed or deferred. Those events were
+/// not handled, so they are allowed to keep propagating.
+pub trait HandlerResult: 'static {
+    const SKIPPED: Self;
+}
+impl HandlerResult for bool {
+    const SKIPPED: Self = false;
+}
+impl HandlerResult for EventPropagation {
+    const SKIPPED: Self = EventPropagation::Continue;
+}
+
+/// Tracks the quiet period of a debounce.
+#[derive(Clone, Copy, Debug)]
+pub struct Debouncer {
+    wait: Duration,
+    last_event: Option<Instant>,
+}
+impl Debouncer {
+    pub fn new(wait: Duration) -> Self {
+        Self {
+            wait,
+            last_event: None,
+        }
+    }
+
+    /// Record an event. The returned instant is when the handler should run if nothing else
+    /// arrives before then.
+    pub fn event(&mut self, now: Instant) -> Instant {
+        self.last_event = Some(now);
+        now + self.wait
+    }
+
+    /// Whether a timer scheduled by [`Debouncer::event`] should fire at `now`. Timers scheduled
+    /// before a later event was recorded return false.
+    pub fn should_fire(&mut self, now: Instant) -> bool {
+        match self.last_event {
+            Some(last) if now.duration_since(last) >= self.wait => {
+                self.last_event = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrottleEdge {
+    /// Run on the first event of a window and drop the rest
+    Leading,
+    /// Run once at the end of each window with the latest event
+    Trailing,
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrottleDecision {
+    /// Run the handler now
+    Run,
+    /// Run the handler with the latest event at the given instant
+    Schedule(Instant),
+    /// A trailing call is already scheduled; just remember the event
+    Defer,
+    Drop,
+}
+
+/// Tracks the windows of a throttle.
+#[derive(Clone, Copy, Debug)]
+pub struct Throttler {
+    interval: Duration,
+    edge: ThrottleEdge,
+    window_end: Option<Instant>,
+    trailing_pending: bool,
+}
+impl Throttler {
+    pub fn new(interval: Duration, edge: ThrottleEdge) -> Self {
+        Self {
+            interval,
+            edge,
+            window_end: None,
+            trailing_pending: false,
+        }
+    }
+
+    pub fn event(&mut self, now: Instant) -> ThrottleDecision {
+        let in_window = self.window_end.is_some_and(|end| now < end);
+        if !in_window {
+            self.window_end = Some(now + self.interval);
+            self.trailing_pending = false;
+            return match self.edge {
+                ThrottleEdge::Leading | ThrottleEdge::Both => ThrottleDecision::Run,
+                ThrottleEdge::Trailing => {
+                    self.trailing_pending = true;
+                    ThrottleDecision::Schedule(now + self.interval)
+                }
+            };
+        }
+        match (self.edge, self.trailing_pending) {
+            (ThrottleEdge::Leading, _) => ThrottleDecision::Drop,
+            (_, true) => ThrottleDecision::Defer,
+            (_, false) => {
+                self.trailing_pending = true;
+                ThrottleDecision::Schedule(self.window_end.unwrap())
+            }
+        }
+    }
+
+    /// Called when a scheduled trailing call fires. Returns whether the handler should run.
+    pub fn trailing_fired(&mut self) -> bool {
+        std::mem::take(&mut self.trailing_pending)
+    }
+}
+
+struct Timer {
+    at: Instant,
+    /// Breaks ties between timers due at the same instant
+    seq: usize,
+    action: Box<dyn FnOnce()>,
+}
+
+struct ClockState {
+    now: Instant,
+    timers: Vec<Timer>,
+    next_seq: usize,
+}
+
+thread_local! {
+    static VIRTUAL_CLOCK: RefCell<Option<ClockState>> = RefCell::new(None);
+}
+
+pub(crate) fn now() -> Instant {
+    VIRTUAL_CLOCK
+        .with(|clock| clock.borrow().as_ref().map(|clock| clock.now))
+        .unwrap_or_else(Instant::now)
+}
+
+pub(crate) fn schedule_at(at: Instant, action: impl FnOnce() + 'static) {
+    let action: Box<dyn FnOnce()> = Box::new(action);
+    let action = VIRTUAL_CLOCK.with(|clock| match clock.borrow_mut().as_mut() {
+        Some(clock) => {
+            let seq = clock.next_seq;
+            clock.next_seq += 1;
+            clock.timers.push(Timer { at, seq, action });
+            None
+        }
+        None => Some(action),
+    });
+    if let Some(action) = action {
+        exec_after(at.saturating_duration_since(Instant::now()), move |_| {
+            action()
+        });
+    }
+}
+
+/// A stand-in for the system clock. While one is running on the current thread the combinators
+/// read the time from it and queue their delayed calls on it instead of on floem timers, so they
+/// can be driven step by step without an event loop. Dropping it goes back to the system clock
+/// and discards the calls still queued.
+pub struct VirtualClock {
+    // the clock lives in a thread local, so keep it on the thread that started it
+    _thread: PhantomData<*const ()>,
+}
+impl VirtualClock {
+    /// Panics if a clock is already running on this thread.
+    pub fn start(now: Instant) -> Self {
+        VIRTUAL_CLOCK.with(|clock| {
+            let mut clock = clock.borrow_mut();
+            assert!(clock.is_none(), "a virtual clock is already running");
+            *clock = Some(ClockState {
+                now,
+                timers: Vec::new(),
+                next_seq: 0,
+            });
+        });
+        Self {
+            _thread: PhantomData,
+        }
+    }
+
+    pub fn now(&self) -> Instant {
+        now()
+    }
+
+    /// Move the time forward to `to`, running the queued calls that come due on the way in the
+    /// order they are due.
+    pub fn advance_to(&self, to: Instant) {
+        while let Some(action) = Self::pop_due(Some(to)) {
+            action();
+        }
+        VIRTUAL_CLOCK.with(|clock| {
+            if let Some(clock) = clock.borrow_mut().as_mut() {
+                clock.now = clock.now.max(to);
+            }
+        });
+    }
+
+    /// Run every queued call, including the ones queued while doing so, moving the time forward
+    /// to each.
+    pub fn run_pending(&self) {
+        while let Some(action) = Self::pop_due(None) {
+            action();
+        }
+    }
+
+    fn pop_due(until: Option<Instant>) -> Option<Box<dyn FnOnce()>> {
+        VIRTUAL_CLOCK.with(|clock| {
+            let mut clock = clock.borrow_mut();
+            let clock = clock.as_mut()?;
+            let idx = clock
+                .timers
+                .iter()
+                .enumerate()
+                .filter(|(_, timer)| !until.is_some_and(|until| timer.at > until))
+                .min_by_key(|(_, timer)| (timer.at, timer.seq))
+                .map(|(idx, _)| idx)?;
+            let timer = clock.timers.swap_remove(idx);
+            clock.now = clock.now.max(timer.at);
+            // run outside the borrow so the call can schedule more
+            Some(timer.action)
+        })
+    }
+}
+impl Drop for VirtualClock {
+    fn drop(&mut self) {
+        // drop the queued calls after releasing the borrow
+        let state = VIRTUAL_CLOCK.with(|clock| clock.borrow_mut().take());
+        drop(state);
+    }
+}
+
+/// Run `handler` with the last event once no event has arrived for `wait`.
+pub fn debounce<R: HandlerResult>(
+    wait: Duration,
+    handler: impl Fn(&Event) -> R + 'static,
+) -> impl Fn(&Event) -> R + 'static {
+    let handler = Rc::new(handler);
+    let debouncer = Rc::new(RefCell::new(Debouncer::new(wait)));
+    let latest: Rc<RefCell<Option<Event>>> = Rc::new(RefCell::new(None));
+    move |event| {
+        *latest.borrow_mut() = Some(event.clone());
+        let fire_at = debouncer.borrow_mut().event(now());
+        let (handler, debouncer, latest) = (handler.clone(), debouncer.clone(), latest.clone());
+        schedule_at(fire_at, move || {
+            if debouncer.borrow_mut().should_fire(now()) {
+                if let Some(event) = latest.borrow_mut().take() {
+                    handler(&event);
+                }
+            }
+        });
+        R::SKIPPED
+    }
+}
+
+/// Run `handler` at most once per `interval`.
+pub fn throttle<R: HandlerResult>(
+    interval: Duration,
+    edge: ThrottleEdge,
+    handler: impl Fn(&Event) -> R + 'static,
+) -> impl Fn(&Event) -> R + 'static {
+    let handler = Rc::new(handler);
+    let throttler = Rc::new(RefCell::new(Throttler::new(interval, edge)));
+    let latest: Rc<RefCell<Option<Event>>> = Rc::new(RefCell::new(None));
+    move |event| {
+        let decision = throttler.borrow_mut().event(now());
+        match decision {
+            ThrottleDecision::Run => handler(event),
+            ThrottleDecision::Schedule(at) => {
+                *latest.borrow_mut() = Some(event.clone());
+                let (handler, throttler, latest) =
+                    (handler.clone(), throttler.clone(), latest.clone());
+                schedule_at(at, move || {
+                    if throttler.borrow_mut().trailing_fired() {
+                        if let Some(event) = latest.borrow_mut().take() {
+                            handler(&event);
+                        }
+                    }
+                });
+                R::SKIPPED
+            }
+            ThrottleDecision::Defer => {
+                *latest.borrow_mut() = Some(event.clone());
+                R::SKIPPED
+            }
+            ThrottleDecision::Drop => R::SKIPPED,
+        }
+    }
+}
+
+/// Run `handler` for the first event only.
+pub fn once<R: HandlerResult>(
+    handler: impl Fn(&Event) -> R + 'static,
+) -> impl Fn(&Event) -> R + 'static {
+    let done = Cell::new(false);
+    move |event| {
+        if done.replace(true) {
+            R::SKIPPED
+        } else {
+            handler(event)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use floem::kurbo::Size;
+
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn debounce_fires_after_quiet_period() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(100 * MS);
+
+        assert_eq!(debouncer.event(start), start + 100 * MS);
+        assert!(!debouncer.should_fire(start + 99 * MS));
+        assert!(debouncer.should_fire(start + 100 * MS));
+        // the event was consumed
+        assert!(!debouncer.should_fire(start + 200 * MS));
+    }
+
+    #[test]
+    fn debounce_later_event_restarts_wait() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(100 * MS);
+
+        let first = debouncer.event(start);
+        let second = debouncer.event(start + 60 * MS);
+        assert_eq!(second, start + 160 * MS);
+        // the timer of the first event finds a newer event and does nothing
+        assert!(!debouncer.should_fire(first));
+        assert!(debouncer.should_fire(second));
+    }
+
+    #[test]
+    fn throttle_leading_runs_first_event_of_window() {
+        let start = Instant::now();
+        let mut throttler = Throttler::new(100 * MS, ThrottleEdge::Leading);
+
+        assert_eq!(throttler.event(start), ThrottleDecision::Run);
+        assert_eq!(throttler.event(start + 10 * MS), ThrottleDecision::Drop);
+        assert_eq!(throttler.event(start + 99 * MS), ThrottleDecision::Drop);
+        assert!(!throttler.trailing_fired());
+        assert_eq!(throttler.event(start + 100 * MS), ThrottleDecision::Run);
+    }
+
+    #[test]
+    fn throttle_trailing_runs_once_when_window_closes() {
+        let start = Instant::now();
+        let mut throttler = Throttler::new(100 * MS, ThrottleEdge::Trailing);
+
+        assert_eq!(
+            throttler.event(start),
+            ThrottleDecision::Schedule(start + 100 * MS)
+        );
+        assert_eq!(throttler.event(start + 30 * MS), ThrottleDecision::Defer);
+        assert_eq!(throttler.event(start + 60 * MS), ThrottleDecision::Defer);
+        // the scheduled call fires at the end of the window, only once
+        assert!(throttler.trailing_fired());
+        assert!(!throttler.trailing_fired());
+        assert_eq!(
+            throttler.event(start + 100 * MS),
+            ThrottleDecision::Schedule(start + 200 * MS)
+        );
+    }
+
+    #[test]
+    fn throttle_both_runs_leading_and_trailing() {
+        let start = Instant::now();
+        let mut throttler = Throttler::new(100 * MS, ThrottleEdge::Both);
+
+        assert_eq!(throttler.event(start), ThrottleDecision::Run);
+        assert_eq!(
+            throttler.event(start + 20 * MS),
+            ThrottleDecision::Schedule(start + 100 * MS)
+        );
+        assert_eq!(throttler.event(start + 50 * MS), ThrottleDecision::Defer);
+        assert!(throttler.trailing_fired());
+        // a new window after the trailing call starts with a leading call again
+        assert_eq!(throttler.event(start + 150 * MS), ThrottleDecision::Run);
+        assert!(!throttler.trailing_fired());
+    }
+
+    #[test]
+    fn throttle_both_without_more_events_has_no_trailing_call() {
+        let start = Instant::now();
+        let mut throttler = Throttler::new(100 * MS, ThrottleEdge::Both);
+
+        assert_eq!(throttler.event(start), ThrottleDecision::Run);
+        assert!(!throttler.trailing_fired());
+        assert_eq!(throttler.event(start + 100 * MS), ThrottleDecision::Run);
+    }
+
+    #[test]
+    fn debounce_runs_on_virtual_clock() {
+        let calls = Rc::new(Cell::new(0));
+        let inner = calls.clone();
+        let handler = debounce(100 * MS, move |_| {
+            inner.set(inner.get() + 1);
+            true
+        });
+        let event = Event::WindowResized(Size::new(800., 600.));
+        let clock = VirtualClock::start(Instant::now());
+        let start = clock.now();
+
+        assert!(!handler(&event));
+        clock.advance_to(start + 60 * MS);
+        assert!(!handler(&event));
+        clock.advance_to(start + 159 * MS);
+        assert_eq!(calls.get(), 0);
+        clock.advance_to(start + 160 * MS);
+        assert_eq!(calls.get(), 1);
+        clock.run_pending();
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn throttle_trailing_call_runs_on_virtual_clock() {
+        let calls = Rc::new(Cell::new(0));
+        let inner = calls.clone();
+        let handler = throttle(100 * MS, ThrottleEdge::Both, move |_| {
+            inner.set(inner.get() + 1);
+            true
+        });
+        let event = Event::WindowResized(Size::new(800., 600.));
+        let clock = VirtualClock::start(Instant::now());
+        let start = clock.now();
+
+        assert!(handler(&event));
+        clock.advance_to(start + 40 * MS);
+        assert!(!handler(&event));
+        assert_eq!(calls.get(), 1);
+        clock.run_pending();
+        assert_eq!(calls.get(), 2);
+        assert_eq!(clock.now(), start + 100 * MS);
+    }
+
+    #[test]
+    fn once_runs_handler_for_first_event_only() {
+        let calls = Rc::new(Cell::new(0));
+        let inner = calls.clone();
+        let handler = once(move |_| {
+            inner.set(inner.get() + 1);
+            true
+        });
+        let event = Event::WindowResized(Size::new(800., 600.));
+
+        assert!(handler(&event));
+        assert!(!handler(&event));
+        assert!(!handler(&event));
+        assert_eq!(calls.get(), 1);
+    }
+}
@@ -0,0 +1,901 @@
+use std::{cell::RefCell, collections::HashMap, fmt, path::Path, rc::Rc, str::FromStr};
+
+use floem::{
+    event::{Event, EventListener},
+    keyboard::{Key, ModifiersState, NamedKey},
+    reactive::use_context,
+    views::Decorators,
+    EventPropagation,
+};
+
+/// The modifier keys held down for a single key press.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+impl Modifiers {
+    fn count(self) -> usize {
+        [self.ctrl, self.shift, self.alt, self.meta]
+            .into_iter()
+            .filter(|held| *held)
+            .count()
+    }
+}
+impl From<ModifiersState> for Modifiers {
+    fn from(value: ModifiersState) -> Self {
+        Self {
+            ctrl: value.control_key(),
+            shift: value.shift_key(),
+            alt: value.alt_key(),
+            meta: value.super_key(),
+        }
+    }
+}
+
+/// A single key press such as `Ctrl+Shift+P`.
+///
+/// The key is stored lowercased so that `Ctrl+P` and `ctrl+p` compare equal, and with
+/// alternative spellings such as `Escape` or `ArrowUp` mapped to the names bindings use.
+///
+/// Symbols and digits are bound by the character they type, without Shift: the keyboard
+/// reports Shift+= as `+`, so the binding is `Ctrl++`. `Ctrl+Shift+=` is rejected when parsing
+/// since it depends on the layout.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyPress {
+    pub key: String,
+    pub mods: Modifiers,
+}
+impl KeyPress {
+    pub fn new(key: impl Into<String>, mods: Modifiers) -> Self {
+        Self {
+            key: canonical_key(&key.into()),
+            mods,
+        }
+    }
+
+    /// Build a key press from a floem key down event. Returns `None` for other events and for
+    /// presses of a lone modifier key.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let Event::KeyDown(key_event) = event else {
+            return None;
+        };
+        Self::from_key(&key_event.key.logical_key, key_event.modifiers.into())
+    }
+
+    pub fn from_key(key: &Key, mods: Modifiers) -> Option<Self> {
+        let key = match key {
+            Key::Character(c) => c.to_string(),
+            Key::Named(named) => named_key_name(named),
+            _ => return None,
+        };
+        if matches!(
+            key.to_lowercase().as_str(),
+            "control" | "shift" | "alt" | "super" | "meta"
+        ) {
+            return None;
+        }
+        // Shift already went into picking the character
+        let mods = Modifiers {
+            shift: mods.shift && !is_shifted_symbol(&key),
+            ..mods
+        };
+        Some(Self::new(key, mods))
+    }
+}
+
+/// Whether `key` is a single character without case, i.e. one that Shift changes into another
+/// character rather than capitalizing.
+fn is_shifted_symbol(key: &str) -> bool {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => !c.is_whitespace() && c.to_lowercase().eq(c.to_uppercase()),
+        _ => false,
+    }
+}
+
+/// The name a binding uses for a named key, e.g. `Esc` or `Up`.
+fn named_key_name(named: &NamedKey) -> String {
+    let name = match named {
+        NamedKey::ArrowUp => "Up",
+        NamedKey::ArrowDown => "Down",
+        NamedKey::ArrowLeft => "Left",
+        NamedKey::ArrowRight => "Right",
+        NamedKey::Escape => "Esc",
+        NamedKey::Enter => "Enter",
+        NamedKey::Tab => "Tab",
+        NamedKey::Space => "Space",
+        NamedKey::Backspace => "Backspace",
+        NamedKey::Delete => "Delete",
+        NamedKey::Insert => "Insert",
+        NamedKey::Home => "Home",
+        NamedKey::End => "End",
+        NamedKey::PageUp => "PageUp",
+        NamedKey::PageDown => "PageDown",
+        // function keys and the rest already use their binding names, e.g. `F5`
+        other => return format!("{other:?}"),
+    };
+    name.to_string()
+}
+
+/// Lowercase `key` and map the alternative spellings bindings accept to one name.
+fn canonical_key(key: &str) -> String {
+    let key = key.to_lowercase();
+    let alias = match key.as_str() {
+        "escape" => "esc",
+        "arrowup" => "up",
+        "arrowdown" => "down",
+        "arrowleft" => "left",
+        "arrowright" => "right",
+        "return" => "enter",
+        "del" => "delete",
+        "ins" => "insert",
+        "pgup" => "pageup",
+        "pgdn" | "pgdown" => "pagedown",
+        " " => "space",
+        _ => return key,
+    };
+    alias.to_string()
+}
+
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mods = [
+            (self.mods.ctrl, "Ctrl"),
+            (self.mods.shift, "Shift"),
+            (self.mods.alt, "Alt"),
+            (self.mods.meta, "Meta"),
+        ];
+        for (_, name) in mods.iter().filter(|(held, _)| *held) {
+            write!(f, "{name}+")?;
+        }
+        let mut chars = self.key.chars();
+        match chars.next() {
+            Some(first) => write!(f, "{}{}", first.to_uppercase(), chars.as_str()),
+            None => Ok(()),
+        }
+    }
+}
+impl FromStr for KeyPress {
+    type Err = ParseKeyBindingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseKeyBindingError::Empty);
+        }
+        // `Ctrl++` binds the plus key itself
+        let (mods_part, key) = if let Some(stripped) = s.strip_suffix("++") {
+            (stripped, "+")
+        } else if s == "+" {
+            ("", "+")
+        } else {
+            match s.rsplit_once('+') {
+                Some((mods, key)) => (mods, key),
+                None => ("", s),
+            }
+        };
+        if key.is_empty() {
+            return Err(ParseKeyBindingError::MissingKey(s.to_string()));
+        }
+
+        let mut mods = Modifiers::default();
+        for modifier in mods_part.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => mods.ctrl = true,
+                "shift" => mods.shift = true,
+                "alt" | "option" => mods.alt = true,
+                "meta" | "cmd" | "super" | "win" => mods.meta = true,
+                // the platform's primary modifier
+                "mod" => {
+                    if cfg!(target_os = "macos") {
+                        mods.meta = true
+                    } else {
+                        mods.ctrl = true
+                    }
+                }
+                other => return Err(ParseKeyBindingError::UnknownModifier(other.to_string())),
+            }
+        }
+        if mods.shift && is_shifted_symbol(key) {
+            return Err(ParseKeyBindingError::ShiftedSymbol(s.to_string()));
+        }
+        Ok(Self::new(key, mods))
+    }
+}
+
+/// A sequence of one or more key presses, e.g. `Ctrl+K Ctrl+S`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBinding(pub Vec<KeyPress>);
+impl KeyBinding {
+    fn starts_with(&self, prefix: &[KeyPress]) -> bool {
+        self.0.len() >= prefix.len() && self.0[..prefix.len()] == *prefix
+    }
+
+    fn modifier_count(&self) -> usize {
+        self.0.iter().map(|press| press.mods.count()).sum()
+    }
+}
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let presses: Vec<String> = self.0.iter().map(|press| press.to_string()).collect();
+        write!(f, "{}", presses.join(" "))
+    }
+}
+impl FromStr for KeyBinding {
+    type Err = ParseKeyBindingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let presses = s
+            .split_whitespace()
+            .map(KeyPress::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if presses.is_empty() {
+            return Err(ParseKeyBindingError::Empty);
+        }
+        Ok(Self(presses))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseKeyBindingError {
+    Empty,
+    MissingKey(String),
+    UnknownModifier(String),
+    /// Shift combined with a symbol or digit, e.g. `Ctrl+Shift+=` instead of `Ctrl++`
+    ShiftedSymbol(String),
+    /// A line of an overrides file that isn't `binding = command`. Holds the line number.
+    InvalidLine(usize),
+}
+impl fmt::Display for ParseKeyBindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty key binding"),
+            Self::MissingKey(press) => write!(f, "no key in `{press}`"),
+            Self::UnknownModifier(modifier) => write!(f, "unknown modifier `{modifier}`"),
+            Self::ShiftedSymbol(press) => {
+                write!(
+                    f,
+                    "`{press}` uses Shift with a symbol, bind the shifted character instead"
+                )
+            }
+            Self::InvalidLine(line) => write!(f, "expected `binding = command` on line {line}"),
+        }
+    }
+}
+impl std::error::Error for ParseKeyBindingError {}
+
+/// Where a binding is active. Bindings in a named scope only fire while focus is inside a view
+/// marked with [`ExtKeymap::keymap_scope`] for that name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KeymapScope {
+    Global,
+    Named(String),
+}
+impl KeymapScope {
+    pub fn named(name: impl Into<String>) -> Self {
+        Self::Named(name.into())
+    }
+}
+
+/// User bindings always win over defaults for the same chord and scope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BindingSource {
+    Default,
+    User,
+}
+
+#[derive(Clone, Debug)]
+struct BindingEntry {
+    binding: KeyBinding,
+    scope: KeymapScope,
+    /// `None` unbinds the chord in this scope
+    command: Option<String>,
+    source: BindingSource,
+    order: usize,
+}
+impl BindingEntry {
+    // higher is more specific
+    fn specificity(&self) -> (BindingSource, usize, usize, usize) {
+        (
+            self.source,
+            self.binding.0.len(),
+            self.binding.modifier_count(),
+            self.order,
+        )
+    }
+}
+
+/// The result of feeding a key press to the keymap.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeymapOutcome {
+    /// A command was run
+    Executed(String),
+    /// The press started or continued a chord and the keymap is waiting for the next press
+    Pending,
+    /// Nothing in this scope matched
+    NoMatch,
+}
+
+#[derive(Default)]
+struct KeymapInner {
+    bindings: Vec<BindingEntry>,
+    commands: HashMap<String, Rc<dyn Fn()>>,
+    pending: Vec<KeyPress>,
+    pending_scope: Option<KeymapScope>,
+    next_order: usize,
+}
+
+/// A shared, cloneable keymap. Provide one with `provide_context(Keymap::new())` at the root of
+/// the app and mark views with [`ExtKeymap::keymap_scope`] / [`ExtKeymap::keymap_root`].
+#[derive(Clone, Default)]
+pub struct Keymap(Rc<RefCell<KeymapInner>>);
+impl Keymap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the action that runs when a binding dispatches `name`.
+    pub fn command(&self, name: impl Into<String>, action: impl Fn() + 'static) -> &Self {
+        self.0
+            .borrow_mut()
+            .commands
+            .insert(name.into(), Rc::new(action));
+        self
+    }
+
+    /// Add a default binding. Panics if `binding` doesn't parse, as defaults are part of the
+    /// program rather than user input.
+    pub fn bind(&self, binding: &str, scope: KeymapScope, command: impl Into<String>) -> &Self {
+        let binding = binding
+            .parse()
+            .unwrap_or_else(|err| panic!("invalid default key binding `{binding}`: {err}"));
+        self.insert(binding, scope, Some(command.into()), BindingSource::Default);
+        self
+    }
+
+    /// Add a user binding that takes precedence over any default for the same chord.
+    pub fn bind_user(
+        &self,
+        binding: KeyBinding,
+        scope: KeymapScope,
+        command: Option<String>,
+    ) -> &Self {
+        self.insert(binding, scope, command, BindingSource::User);
+        self
+    }
+
+    fn insert(
+        &self,
+        binding: KeyBinding,
+        scope: KeymapScope,
+        command: Option<String>,
+        source: BindingSource,
+    ) {
+        let mut inner = self.0.borrow_mut();
+        let order = inner.next_order;
+        inner.next_order += 1;
+        inner.bindings.push(BindingEntry {
+            binding,
+            scope,
+            command,
+            source,
+            order,
+        });
+    }
+
+    /// Load user overrides. The format is one `binding = command` per line, with `[scope]`
+    /// headers switching the scope of the following lines (`[global]` switches back). A command
+    /// of `-` unbinds the chord. Blank lines and lines starting with `#` are ignored.
+    ///
+    /// ```text
+    /// Ctrl+Shift+P = palette.open
+    /// [editor]
+    /// Ctrl+K Ctrl+S = file.save_all
+    /// Ctrl+D = -
+    /// ```
+    pub fn load_overrides_str(&self, source: &str) -> Result<(), ParseKeyBindingError> {
+        let mut scope = KeymapScope::Global;
+        let mut parsed = Vec::new();
+        for (idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                scope = match name.trim() {
+                    "global" => KeymapScope::Global,
+                    name => KeymapScope::named(name),
+                };
+                continue;
+            }
+            let Some((binding, command)) = line.rsplit_once('=') else {
+                return Err(ParseKeyBindingError::InvalidLine(idx + 1));
+            };
+            let command = match command.trim() {
+                "" => return Err(ParseKeyBindingError::InvalidLine(idx + 1)),
+                "-" => None,
+                command => Some(command.to_string()),
+            };
+            parsed.push((binding.parse()?, scope.clone(), command));
+        }
+        // only apply the file once every line is known to be valid
+        for (binding, scope, command) in parsed {
+            self.bind_user(binding, scope, command);
+        }
+        Ok(())
+    }
+
+    pub fn load_overrides(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let source = std::fs::read_to_string(path)?;
+        self.load_overrides_str(&source)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    /// The binding that currently runs `command` in `scope`, for showing shortcut hints in menus.
+    pub fn binding_for(&self, command: &str, scope: &KeymapScope) -> Option<KeyBinding> {
+        let inner = self.0.borrow();
+        inner
+            .bindings
+            .iter()
+            .filter(|entry| &entry.scope == scope && entry.command.as_deref() == Some(command))
+            .filter(|entry| {
+                // skip bindings that a more specific entry has taken over
+                Self::winner(&inner.bindings, scope, &entry.binding)
+                    .is_some_and(|winner| winner.order == entry.order)
+            })
+            .max_by_key(|entry| entry.specificity())
+            .map(|entry| entry.binding.clone())
+    }
+
+    fn winner<'a>(
+        bindings: &'a [BindingEntry],
+        scope: &KeymapScope,
+        binding: &KeyBinding,
+    ) -> Option<&'a BindingEntry> {
+        bindings
+            .iter()
+            .filter(|entry| &entry.scope == scope && &entry.binding == binding)
+            .max_by_key(|entry| entry.specificity())
+    }
+
+    /// The bindings of `scope` that `sequence` is a prefix of, minus those that are overridden
+    /// or unbound.
+    fn candidates<'a>(
+        bindings: &'a [BindingEntry],
+        scope: &KeymapScope,
+        sequence: &[KeyPress],
+    ) -> Vec<&'a BindingEntry> {
+        bindings
+            .iter()
+            .filter(|entry| &entry.scope == scope && entry.binding.starts_with(sequence))
+            .filter(|entry| {
+                Self::winner(bindings, scope, &entry.binding)
+                    .is_some_and(|winner| winner.order == entry.order)
+            })
+            .filter(|entry| entry.command.is_some())
+            .collect()
+    }
+
+    /// Feed a key press to the bindings of a single scope. Views call this through
+    /// [`ExtKeymap::keymap_scope`]; it is public so tests and replayed sessions can drive the
+    /// keymap without a window.
+    pub fn dispatch(&self, press: KeyPress, scope: &KeymapScope) -> KeymapOutcome {
+        enum Step {
+            Done(KeymapOutcome),
+            Run(String, Option<Rc<dyn Fn()>>),
+            /// The chord broke off; try the press on its own
+            Retry,
+        }
+
+        let step = {
+            let mut inner = self.0.borrow_mut();
+            if let Some(pending_scope) = inner.pending_scope.clone().filter(|s| s != scope) {
+                let mut sequence = inner.pending.clone();
+                sequence.push(press.clone());
+                // presses bubble from inner scopes outwards, so leave one that continues the
+                // chord to the scope the chord was started in
+                if !Self::candidates(&inner.bindings, &pending_scope, &sequence).is_empty() {
+                    return KeymapOutcome::NoMatch;
+                }
+                // anything else means focus has moved on and the chord is abandoned
+                inner.pending.clear();
+                inner.pending_scope = None;
+            }
+            let mut sequence = inner.pending.clone();
+            sequence.push(press.clone());
+            let candidates = Self::candidates(&inner.bindings, scope, &sequence);
+
+            // prefer waiting for a longer chord over firing a shorter one with the same prefix
+            let longer = candidates
+                .iter()
+                .any(|entry| entry.binding.0.len() > sequence.len());
+            let exact = candidates
+                .iter()
+                .filter(|entry| entry.binding.0.len() == sequence.len())
+                .max_by_key(|entry| entry.specificity())
+                .and_then(|entry| entry.command.clone());
+
+            match (exact, longer) {
+                (_, true) => {
+                    inner.pending = sequence;
+                    inner.pending_scope = Some(scope.clone());
+                    Step::Done(KeymapOutcome::Pending)
+                }
+                (Some(command), false) => {
+                    inner.pending.clear();
+                    inner.pending_scope = None;
+                    let action = inner.commands.get(&command).cloned();
+                    Step::Run(command, action)
+                }
+                (None, false) => {
+                    let broke_chord = sequence.len() > 1;
+                    inner.pending.clear();
+                    inner.pending_scope = None;
+                    if broke_chord {
+                        Step::Retry
+                    } else {
+                        Step::Done(KeymapOutcome::NoMatch)
+                    }
+                }
+            }
+        };
+        // run outside the borrow so commands can rebind keys
+        match step {
+            Step::Done(outcome) => outcome,
+            Step::Run(command, action) => {
+                if let Some(action) = action {
+                    action();
+                }
+                KeymapOutcome::Executed(command)
+            }
+            Step::Retry => self.dispatch(press, scope),
+        }
+    }
+
+    /// Drop any half-typed chord.
+    pub fn reset(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.pending.clear();
+        inner.pending_scope = None;
+    }
+}
+
+pub trait ExtKeymap {
+    /// Dispatch key presses inside this view to bindings of `scope`. Key events bubble from the
+    /// focused view outwards, so nested scopes take precedence over the scopes around them.
+    fn keymap_scope(self, scope: KeymapScope) -> Self;
+
+    /// Dispatch key presses that reach this view to global bindings. Put this on the root view.
+    fn keymap_root(self) -> Self;
+}
+impl<T: Decorators> ExtKeymap for T {
+    fn keymap_scope(self, scope: KeymapScope) -> Self {
+        let keymap = use_context::<Keymap>().expect("Expected a Keymap to have been provided");
+        self.on_event(EventListener::KeyDown, move |event| {
+            let Some(press) = KeyPress::from_event(event) else {
+                return EventPropagation::Continue;
+            };
+            match keymap.dispatch(press, &scope) {
+                KeymapOutcome::NoMatch => EventPropagation::Continue,
+                _ => EventPropagation::Stop,
+            }
+        })
+    }
+
+    fn keymap_root(self) -> Self {
+        self.keymap_scope(KeymapScope::Global)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn press(s: &str) -> KeyPress {
+        s.parse().unwrap()
+    }
+
+    fn ctrl() -> Modifiers {
+        Modifiers {
+            ctrl: true,
+            ..Default::default()
+        }
+    }
+
+    fn counter(keymap: &Keymap, command: &str) -> Rc<Cell<usize>> {
+        let count = Rc::new(Cell::new(0));
+        let inner = count.clone();
+        keymap.command(command, move || inner.set(inner.get() + 1));
+        count
+    }
+
+    fn editor() -> KeymapScope {
+        KeymapScope::named("editor")
+    }
+
+    #[test]
+    fn parses_modifiers_and_key() {
+        let parsed = press("Ctrl+Shift+P");
+        assert_eq!(parsed.key, "p");
+        assert!(parsed.mods.ctrl && parsed.mods.shift);
+        assert!(!parsed.mods.alt && !parsed.mods.meta);
+        assert_eq!(press("ctrl+shift+p"), parsed);
+        assert_eq!(parsed.to_string(), "Ctrl+Shift+P");
+    }
+
+    #[test]
+    fn parses_plus_key() {
+        assert_eq!(press("Ctrl++"), KeyPress::new("+", ctrl()));
+        assert_eq!(press("+"), KeyPress::new("+", Modifiers::default()));
+    }
+
+    #[test]
+    fn shifted_symbols_match_without_shift() {
+        let shift_ctrl = Modifiers {
+            shift: true,
+            ..ctrl()
+        };
+        let plus = KeyPress::from_key(&Key::Character("+".into()), shift_ctrl);
+        assert_eq!(plus, Some(press("Ctrl++")));
+        let bang = KeyPress::from_key(&Key::Character("!".into()), shift_ctrl);
+        assert_eq!(bang, Some(press("Ctrl+!")));
+        // letters keep Shift
+        let p = KeyPress::from_key(&Key::Character("P".into()), shift_ctrl);
+        assert_eq!(p, Some(press("Ctrl+Shift+P")));
+
+        assert_eq!(
+            "Ctrl+Shift+=".parse::<KeyPress>(),
+            Err(ParseKeyBindingError::ShiftedSymbol(
+                "Ctrl+Shift+=".to_string()
+            ))
+        );
+        assert_eq!(
+            "Shift+1".parse::<KeyPress>(),
+            Err(ParseKeyBindingError::ShiftedSymbol("Shift+1".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_key_aliases() {
+        assert_eq!(press("Escape"), press("Esc"));
+        assert_eq!(press("Ctrl+ArrowUp"), press("Ctrl+Up"));
+        assert_eq!(press("PgDn"), press("PageDown"));
+        assert_eq!(press("Return"), press("Enter"));
+    }
+
+    #[test]
+    fn rejects_invalid_bindings() {
+        assert_eq!("".parse::<KeyBinding>(), Err(ParseKeyBindingError::Empty));
+        assert_eq!(
+            "Ctrl+".parse::<KeyPress>(),
+            Err(ParseKeyBindingError::MissingKey("Ctrl+".to_string()))
+        );
+        assert_eq!(
+            "Hyper+K".parse::<KeyPress>(),
+            Err(ParseKeyBindingError::UnknownModifier("hyper".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_and_displays_chords() {
+        let binding: KeyBinding = "Ctrl+K  Ctrl+S".parse().unwrap();
+        assert_eq!(binding.0, vec![press("Ctrl+K"), press("Ctrl+S")]);
+        assert_eq!(binding.to_string(), "Ctrl+K Ctrl+S");
+    }
+
+    #[test]
+    fn named_keys_use_binding_names() {
+        let up = KeyPress::from_key(&Key::Named(NamedKey::ArrowUp), ctrl());
+        assert_eq!(up, Some(press("Ctrl+Up")));
+        let esc = KeyPress::from_key(&Key::Named(NamedKey::Escape), Modifiers::default());
+        assert_eq!(esc, Some(press("Esc")));
+        let f5 = KeyPress::from_key(&Key::Named(NamedKey::F5), Modifiers::default());
+        assert_eq!(f5, Some(press("F5")));
+        let space = KeyPress::from_key(&Key::Named(NamedKey::Space), Modifiers::default());
+        assert_eq!(space, Some(press("Space")));
+        assert_eq!(
+            KeyPress::from_key(&Key::Named(NamedKey::Control), ctrl()),
+            None
+        );
+    }
+
+    #[test]
+    fn dispatches_single_press() {
+        let keymap = Keymap::new();
+        let saves = counter(&keymap, "save");
+        keymap.bind("Ctrl+S", KeymapScope::Global, "save");
+
+        let outcome = keymap.dispatch(press("Ctrl+S"), &KeymapScope::Global);
+        assert_eq!(outcome, KeymapOutcome::Executed("save".to_string()));
+        assert_eq!(saves.get(), 1);
+        let outcome = keymap.dispatch(press("Ctrl+S"), &editor());
+        assert_eq!(outcome, KeymapOutcome::NoMatch);
+        assert_eq!(saves.get(), 1);
+    }
+
+    #[test]
+    fn chord_waits_for_second_press() {
+        let keymap = Keymap::new();
+        let saves = counter(&keymap, "save_all");
+        keymap.bind("Ctrl+K Ctrl+S", KeymapScope::Global, "save_all");
+
+        let global = KeymapScope::Global;
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+K"), &global),
+            KeymapOutcome::Pending
+        );
+        assert_eq!(saves.get(), 0);
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+S"), &global),
+            KeymapOutcome::Executed("save_all".to_string())
+        );
+        assert_eq!(saves.get(), 1);
+    }
+
+    #[test]
+    fn longer_chord_is_preferred_over_prefix() {
+        let keymap = Keymap::new();
+        keymap.bind("Ctrl+K", KeymapScope::Global, "short").bind(
+            "Ctrl+K Ctrl+S",
+            KeymapScope::Global,
+            "long",
+        );
+
+        let global = KeymapScope::Global;
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+K"), &global),
+            KeymapOutcome::Pending
+        );
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+S"), &global),
+            KeymapOutcome::Executed("long".to_string())
+        );
+    }
+
+    #[test]
+    fn broken_chord_retries_the_press_alone() {
+        let keymap = Keymap::new();
+        let palettes = counter(&keymap, "palette");
+        keymap
+            .bind("Ctrl+K Ctrl+S", KeymapScope::Global, "save_all")
+            .bind("Ctrl+P", KeymapScope::Global, "palette");
+
+        let global = KeymapScope::Global;
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+K"), &global),
+            KeymapOutcome::Pending
+        );
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+P"), &global),
+            KeymapOutcome::Executed("palette".to_string())
+        );
+        assert_eq!(palettes.get(), 1);
+        // the chord is gone, so a following Ctrl+S doesn't complete it
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+S"), &global),
+            KeymapOutcome::NoMatch
+        );
+    }
+
+    #[test]
+    fn chord_is_abandoned_when_another_scope_gets_a_press() {
+        let keymap = Keymap::new();
+        keymap.bind("Ctrl+K Ctrl+S", editor(), "save_all").bind(
+            "Ctrl+P",
+            KeymapScope::Global,
+            "palette",
+        );
+
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+K"), &editor()),
+            KeymapOutcome::Pending
+        );
+        // focus moved out of the editor
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+P"), &KeymapScope::Global),
+            KeymapOutcome::Executed("palette".to_string())
+        );
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+S"), &editor()),
+            KeymapOutcome::NoMatch
+        );
+    }
+
+    #[test]
+    fn chord_continuation_bubbles_to_its_scope() {
+        let keymap = Keymap::new();
+        keymap
+            .bind("Ctrl+K Ctrl+S", KeymapScope::Global, "save_all")
+            .bind("Ctrl+S", editor(), "save");
+
+        // presses inside the editor reach the editor scope first, then the global one
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+K"), &editor()),
+            KeymapOutcome::NoMatch
+        );
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+K"), &KeymapScope::Global),
+            KeymapOutcome::Pending
+        );
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+S"), &editor()),
+            KeymapOutcome::NoMatch
+        );
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+S"), &KeymapScope::Global),
+            KeymapOutcome::Executed("save_all".to_string())
+        );
+    }
+
+    #[test]
+    fn user_bindings_override_and_unbind_defaults() {
+        let keymap = Keymap::new();
+        keymap.bind("Ctrl+S", KeymapScope::Global, "save").bind(
+            "Ctrl+D",
+            KeymapScope::Global,
+            "duplicate",
+        );
+        keymap
+            .bind_user(
+                "Ctrl+S".parse().unwrap(),
+                KeymapScope::Global,
+                Some("save_as".to_string()),
+            )
+            .bind_user("Ctrl+D".parse().unwrap(), KeymapScope::Global, None);
+
+        let global = KeymapScope::Global;
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+S"), &global),
+            KeymapOutcome::Executed("save_as".to_string())
+        );
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+D"), &global),
+            KeymapOutcome::NoMatch
+        );
+        assert_eq!(keymap.binding_for("save", &global), None);
+        assert_eq!(
+            keymap.binding_for("save_as", &global),
+            Some("Ctrl+S".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn loads_overrides_with_scopes() {
+        let keymap = Keymap::new();
+        keymap.bind("Ctrl+D", editor(), "duplicate");
+        keymap
+            .load_overrides_str(
+                "# comment\nCtrl+Shift+P = palette\n\n[editor]\nCtrl+K Ctrl+S = save_all\nCtrl+D = -\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+Shift+P"), &KeymapScope::Global),
+            KeymapOutcome::Executed("palette".to_string())
+        );
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+D"), &editor()),
+            KeymapOutcome::NoMatch
+        );
+        assert_eq!(
+            keymap.binding_for("save_all", &editor()),
+            Some("Ctrl+K Ctrl+S".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn invalid_overrides_apply_nothing() {
+        let keymap = Keymap::new();
+        let result = keymap.load_overrides_str("Ctrl+P = palette\nnot a binding\n");
+        assert_eq!(result, Err(ParseKeyBindingError::InvalidLine(2)));
+        assert_eq!(
+            keymap.dispatch(press("Ctrl+P"), &KeymapScope::Global),
+            KeymapOutcome::NoMatch
+        );
+    }
+}
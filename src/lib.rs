@@ -1,8 +1,27 @@
+#[cfg(feature = "tokio_feature")]
+pub mod async_options;
+pub mod click_outside;
+pub mod color_picker;
+pub mod combobox;
 pub mod components;
+pub mod context_menu;
+pub mod drawer;
 pub mod dropdown;
 #[cfg(feature = "tokio_feature")]
 pub mod floem_tokio;
+pub mod font_picker;
+pub mod fuzzy;
+pub mod gesture;
+pub mod handlers;
 pub mod icons;
+pub mod keymap;
+pub mod list_nav;
+pub mod modal;
+pub mod multi_select;
+pub mod pop_over;
 pub mod popover;
+pub mod record;
+pub mod spinner;
 pub mod style;
-// mod pop_over;
+pub mod toast;
+pub mod tooltip;
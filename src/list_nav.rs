@@ -0,0 +1,198 @@
+use std::time::{Duration, Instant};
+
+/// The next index from `from` in direction `step` for which `enabled` holds, wrapping around
+/// the ends when `wrap` is set. With no current index, stepping forward starts at the first
+/// item and stepping backward at the last.
+pub fn next_enabled(
+    from: Option<usize>,
+    len: usize,
+    step: isize,
+    wrap: bool,
+    enabled: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let len_i = len as isize;
+    let direction = if step < 0 { -1 } else { 1 };
+    let mut idx = match from {
+        Some(from) => from as isize + step,
+        None if direction > 0 => 0,
+        None => len_i - 1,
+    };
+    for _ in 0..len {
+        if !(0..len_i).contains(&idx) {
+            if !wrap {
+                return from;
+            }
+            idx = idx.rem_euclid(len_i);
+        }
+        if enabled(idx as usize) {
+            return Some(idx as usize);
+        }
+        idx += direction;
+    }
+    from
+}
+
+/// Move by up to `page` enabled items, stopping at the ends. Used for PageUp/PageDown.
+pub fn page_enabled(
+    from: Option<usize>,
+    len: usize,
+    page: isize,
+    enabled: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    let step = page.signum();
+    let mut current = from;
+    for _ in 0..page.unsigned_abs() {
+        match next_enabled(current, len, step, false, &enabled) {
+            Some(next) if Some(next) != current => current = Some(next),
+            _ => break,
+        }
+    }
+    current
+}
+
+/// Collects typed characters for jumping to list items by their label.
+#[derive(Clone, Debug)]
+pub struct TypeAhead {
+    buffer: String,
+    last: Option<Instant>,
+    timeout: Duration,
+}
+impl Default for TypeAhead {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(700))
+    }
+}
+impl TypeAhead {
+    /// Typing pauses longer than `timeout` start a new search.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            buffer: String::new(),
+            last: None,
+            timeout,
+        }
+    }
+
+    pub fn push(&mut self, text: &str, now: Instant) -> &str {
+        if self
+            .last
+            .is_some_and(|last| now.duration_since(last) > self.timeout)
+        {
+            self.buffer.clear();
+        }
+        self.last = Some(now);
+        self.buffer.push_str(&text.to_lowercase());
+        &self.buffer
+    }
+
+    /// The first enabled item after `current` whose label starts with the typed text. Repeating a
+    /// single character cycles through the items starting with it.
+    pub fn find(
+        &self,
+        current: Option<usize>,
+        len: usize,
+        label: impl Fn(usize) -> String,
+        enabled: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        if self.buffer.is_empty() || len == 0 {
+            return None;
+        }
+        let mut chars = self.buffer.chars();
+        let first = chars.next().unwrap();
+        let repeated = chars.all(|c| c == first);
+        let needle = if repeated {
+            first.to_string()
+        } else {
+            self.buffer.clone()
+        };
+        // a longer search may still match the current item, a repeated key moves past it
+        let start = match current {
+            Some(current) if repeated => current + 1,
+            Some(current) => current,
+            None => 0,
+        };
+        (0..len)
+            .map(|offset| (start + offset) % len)
+            .find(|&idx| enabled(idx) && label(idx).to_lowercase().starts_with(&needle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LABELS: [&str; 6] = ["Apple", "Apricot", "Banana", "Blueberry", "Cherry", "Date"];
+
+    fn label(idx: usize) -> String {
+        LABELS[idx].to_string()
+    }
+
+    #[test]
+    fn steps_wrap_only_when_asked() {
+        let all = |_| true;
+        assert_eq!(next_enabled(None, 6, 1, false, all), Some(0));
+        assert_eq!(next_enabled(None, 6, -1, false, all), Some(5));
+        assert_eq!(next_enabled(Some(5), 6, 1, false, all), Some(5));
+        assert_eq!(next_enabled(Some(5), 6, 1, true, all), Some(0));
+        assert_eq!(next_enabled(Some(0), 6, -1, false, all), Some(0));
+        assert_eq!(next_enabled(Some(0), 6, -1, true, all), Some(5));
+        assert_eq!(next_enabled(None, 0, 1, true, all), None);
+    }
+
+    #[test]
+    fn steps_skip_disabled_items() {
+        let enabled = |idx| idx != 1 && idx != 2 && idx != 5;
+        assert_eq!(next_enabled(Some(0), 6, 1, false, enabled), Some(3));
+        assert_eq!(next_enabled(Some(3), 6, -1, false, enabled), Some(0));
+        assert_eq!(next_enabled(None, 6, -1, false, enabled), Some(4));
+        assert_eq!(next_enabled(Some(4), 6, 1, false, enabled), Some(4));
+        assert_eq!(next_enabled(Some(4), 6, 1, true, enabled), Some(0));
+        assert_eq!(next_enabled(Some(0), 6, 1, true, |_| false), Some(0));
+    }
+
+    #[test]
+    fn pages_count_enabled_items_and_stop_at_the_ends() {
+        let enabled = |idx| idx != 2;
+        assert_eq!(page_enabled(Some(0), 6, 2, enabled), Some(3));
+        assert_eq!(page_enabled(Some(3), 6, -2, enabled), Some(0));
+        assert_eq!(page_enabled(Some(3), 6, 10, enabled), Some(5));
+        assert_eq!(page_enabled(Some(3), 6, -10, enabled), Some(0));
+        assert_eq!(page_enabled(None, 6, 3, enabled), Some(3));
+    }
+
+    #[test]
+    fn type_ahead_matches_prefixes_and_cycles_on_repeats() {
+        let start = Instant::now();
+        let all = |_| true;
+        let mut type_ahead = TypeAhead::default();
+        type_ahead.push("B", start);
+        assert_eq!(type_ahead.find(None, 6, label, all), Some(2));
+        type_ahead.push("l", start + Duration::from_millis(100));
+        assert_eq!(type_ahead.find(Some(2), 6, label, all), Some(3));
+
+        let mut type_ahead = TypeAhead::default();
+        type_ahead.push("a", start);
+        assert_eq!(type_ahead.find(Some(0), 6, label, all), Some(1));
+        type_ahead.push("a", start + Duration::from_millis(100));
+        assert_eq!(type_ahead.find(Some(1), 6, label, all), Some(0));
+        assert_eq!(type_ahead.find(Some(1), 6, label, |idx| idx != 0), Some(1));
+    }
+
+    #[test]
+    fn type_ahead_starts_over_after_a_pause() {
+        let start = Instant::now();
+        let mut type_ahead = TypeAhead::new(Duration::from_millis(500));
+        assert_eq!(type_ahead.push("c", start), "c");
+        assert_eq!(
+            type_ahead.push("h", start + Duration::from_millis(500)),
+            "ch"
+        );
+        assert_eq!(
+            type_ahead.push("d", start + Duration::from_millis(1001)),
+            "d"
+        );
+        assert_eq!(type_ahead.find(None, 6, label, |_| true), Some(5));
+    }
+}
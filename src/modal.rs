@@ -0,0 +1,357 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+use floem::{
+    action::{add_overlay, exec_after, remove_overlay},
+    event::{Event, EventListener},
+    id::Id,
+    keyboard::{Key, NamedKey},
+    kurbo::Point,
+    reactive::{create_effect, create_rw_signal, RwSignal},
+    style::{Background, BorderColor, TextColor},
+    view::View,
+    views::{container, h_stack, label, v_stack, Decorators},
+    EventPropagation,
+};
+
+use crate::{
+    components::{button, ButtonOptions, ButtonVariant},
+    list_nav::next_enabled,
+    style::{DesignSystem, ExtDynamicColor},
+};
+
+/// The outcome of a dialog, available through [`Dialog::read`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DialogResult<R> {
+    Pending,
+    /// Closed with Escape, the backdrop, or [`DialogCx::dismiss`]
+    Dismissed,
+    Chosen(R),
+}
+
+/// A handle to an open or closed dialog, similar to `floem_tokio::Resource`: read it inside an
+/// effect or view closure to react when the user makes a choice.
+pub struct Dialog<R: 'static> {
+    result: RwSignal<DialogResult<R>>,
+}
+impl<R: 'static> Clone for Dialog<R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<R: 'static> Copy for Dialog<R> {}
+impl<R: Clone + 'static> Dialog<R> {
+    pub fn is_open(&self) -> bool {
+        self.result
+            .with(|result| matches!(result, DialogResult::Pending))
+    }
+
+    pub fn read(&self) -> DialogResult<R> {
+        self.result.get()
+    }
+
+    /// The chosen value, or `None` while pending or when dismissed.
+    pub fn chosen(&self) -> Option<R> {
+        match self.result.get() {
+            DialogResult::Chosen(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+struct FocusMember {
+    id: Id,
+    disabled: Option<Rc<dyn Fn() -> bool>>,
+}
+impl FocusMember {
+    fn is_disabled(&self) -> bool {
+        self.disabled.as_ref().is_some_and(|disabled| disabled())
+    }
+}
+
+/// Cycles keyboard focus between the views registered with it.
+#[derive(Clone, Default)]
+pub struct FocusTrap {
+    members: Rc<RefCell<Vec<FocusMember>>>,
+    current: Rc<Cell<Option<usize>>>,
+}
+impl FocusTrap {
+    /// Make `view` part of the Tab order of the trap. Members are visited in registration order.
+    pub fn member<V: View + Decorators>(&self, view: V) -> V {
+        self.register(view, None)
+    }
+
+    /// Like [`FocusTrap::member`] for a view that can be disabled, such as a button with
+    /// [`ButtonOptions::disabled`]: Tab skips it while `disabled` returns true.
+    pub fn member_disabled<V: View + Decorators>(
+        &self,
+        view: V,
+        disabled: impl Fn() -> bool + 'static,
+    ) -> V {
+        self.register(view, Some(Rc::new(disabled)))
+    }
+
+    fn register<V: View + Decorators>(&self, view: V, disabled: Option<Rc<dyn Fn() -> bool>>) -> V {
+        let idx = {
+            let mut members = self.members.borrow_mut();
+            members.push(FocusMember {
+                id: view.id(),
+                disabled,
+            });
+            members.len() - 1
+        };
+        let current = self.current.clone();
+        view.keyboard_navigatable()
+            .on_event(EventListener::FocusGained, move |_| {
+                current.set(Some(idx));
+                EventPropagation::Continue
+            })
+    }
+
+    /// Focus the first member that isn't disabled.
+    pub fn focus_first(&self) {
+        let members = self.members.borrow();
+        if let Some(first) = next_enabled(None, members.len(), 1, false, |idx| {
+            !members[idx].is_disabled()
+        }) {
+            self.current.set(Some(first));
+            members[first].id.request_focus();
+        }
+    }
+
+    fn step(&self, backwards: bool) {
+        let members = self.members.borrow();
+        let step = if backwards { -1 } else { 1 };
+        let next = next_enabled(self.current.get(), members.len(), step, true, |idx| {
+            !members[idx].is_disabled()
+        });
+        // with every member disabled the focus stays where it is
+        if let Some(next) = next.filter(|next| !members[*next].is_disabled()) {
+            self.current.set(Some(next));
+            members[next].id.request_focus();
+        }
+    }
+}
+
+/// Passed to the content of a dialog to close it and to set up its focus order.
+pub struct DialogCx<R: 'static> {
+    pub dialog: Dialog<R>,
+    pub focus: FocusTrap,
+}
+impl<R: 'static> Clone for DialogCx<R> {
+    fn clone(&self) -> Self {
+        Self {
+            dialog: self.dialog,
+            focus: self.focus.clone(),
+        }
+    }
+}
+impl<R: 'static> DialogCx<R> {
+    pub fn choose(&self, value: R) {
+        self.dialog.result.set(DialogResult::Chosen(value));
+    }
+
+    pub fn dismiss(&self) {
+        self.dialog.result.set(DialogResult::Dismissed);
+    }
+
+    /// Shorthand for [`FocusTrap::member`].
+    pub fn focusable<V: View + Decorators>(&self, view: V) -> V {
+        self.focus.member(view)
+    }
+
+    /// Shorthand for [`FocusTrap::member_disabled`].
+    pub fn focusable_disabled<V: View + Decorators>(
+        &self,
+        view: V,
+        disabled: impl Fn() -> bool + 'static,
+    ) -> V {
+        self.focus.member_disabled(view, disabled)
+    }
+}
+
+pub struct ModalOptions {
+    /// Clicking the backdrop dismisses the dialog
+    pub close_on_backdrop: bool,
+    /// Asked before Escape or the backdrop dismisses the dialog. Returning false keeps it open.
+    pub can_dismiss: Rc<dyn Fn() -> bool>,
+}
+impl Default for ModalOptions {
+    fn default() -> Self {
+        Self {
+            close_on_backdrop: false,
+            can_dismiss: Rc::new(|| true),
+        }
+    }
+}
+
+/// Open a modal dialog above the whole window. Every dialog gets its own overlay, so a dialog
+/// can open another one on top of itself; Escape only reaches the topmost one because it holds
+/// the focus.
+pub fn open_dialog<DS, R, V>(
+    options: ModalOptions,
+    content: impl FnOnce(DialogCx<R>) -> V + 'static,
+) -> Dialog<R>
+where
+    DS: DesignSystem + 'static,
+    R: 'static,
+    V: View + 'static,
+{
+    let dialog = Dialog {
+        result: create_rw_signal(DialogResult::Pending),
+    };
+    let cx = DialogCx {
+        dialog,
+        focus: FocusTrap::default(),
+    };
+
+    let try_dismiss = {
+        let cx = cx.clone();
+        let can_dismiss = options.can_dismiss.clone();
+        move || {
+            if can_dismiss() {
+                cx.dismiss();
+            }
+        }
+    };
+    let escape_dismiss = try_dismiss.clone();
+    let close_on_backdrop = options.close_on_backdrop;
+    let focus = cx.focus.clone();
+
+    let overlay = add_overlay(Point::ZERO, move |_| {
+        let panel = container(content(cx.clone()))
+            .keyboard_navigatable()
+            .on_event(EventListener::KeyDown, move |event| {
+                let Event::KeyDown(key_event) = event else {
+                    return EventPropagation::Continue;
+                };
+                match &key_event.key.logical_key {
+                    Key::Named(NamedKey::Escape) => escape_dismiss(),
+                    Key::Named(NamedKey::Tab) => {
+                        cx.focus.step(key_event.modifiers.shift_key());
+                    }
+                    _ => return EventPropagation::Continue,
+                }
+                EventPropagation::Stop
+            })
+            // keep clicks on the panel from reaching the backdrop
+            .on_event(EventListener::PointerDown, |_| EventPropagation::Stop)
+            .style(|s| {
+                s.min_width(280.)
+                    .max_width_pct(80.)
+                    .padding(DS::PADDING * 2.)
+                    .border(DS::BORDER_NORMAL)
+                    .border_radius(DS::BORDER_RADIUS)
+                    .ld_color(Background, DS::BACKGROUND.base)
+                    .ld_color(BorderColor, DS::BACKGROUND_3.base)
+                    .ld_color(TextColor, DS::TEXT.base)
+            });
+        let panel_id = panel.id();
+        exec_after(Duration::ZERO, move |_| {
+            if focus.members.borrow().is_empty() {
+                panel_id.request_focus();
+            } else {
+                focus.focus_first();
+            }
+        });
+
+        container(panel)
+            // the backdrop swallows pointer input meant for the views behind it
+            .on_event(EventListener::PointerDown, move |_| {
+                if close_on_backdrop {
+                    try_dismiss();
+                }
+                EventPropagation::Stop
+            })
+            .on_event(EventListener::PointerUp, |_| EventPropagation::Stop)
+            .on_event(EventListener::PointerMove, |_| EventPropagation::Stop)
+            .on_event(EventListener::PointerEnter, |_| EventPropagation::Stop)
+            .on_event(EventListener::PointerLeave, |_| EventPropagation::Stop)
+            .on_event(EventListener::PointerWheel, |_| EventPropagation::Stop)
+            .style(|s| {
+                s.size_full()
+                    .items_center()
+                    .justify_center()
+                    .ld_color(Background, DS::BACKDROP)
+            })
+    });
+
+    create_effect(move |removed| {
+        let open = dialog
+            .result
+            .with(|result| matches!(result, DialogResult::Pending));
+        if !open && removed != Some(true) {
+            remove_overlay(overlay);
+            return true;
+        }
+        removed.unwrap_or(false)
+    });
+
+    dialog
+}
+
+fn message_body<DS: DesignSystem + 'static>(
+    title: String,
+    message: String,
+    buttons: impl View + 'static,
+) -> impl View {
+    v_stack((
+        label(move || title.clone()).style(|s| s.font_size(DS::BASE_FONT_SIZE * 1.25)),
+        label(move || message.clone()),
+        buttons,
+    ))
+    .style(|s| s.flex_col().gap(0., DS::PADDING * 1.5))
+}
+
+/// Ask a yes/no question. Resolves to `Chosen(true)` for OK, `Chosen(false)` for Cancel and
+/// `Dismissed` for Escape.
+pub fn confirm<DS: DesignSystem + 'static>(
+    title: impl Into<String>,
+    message: impl Into<String>,
+) -> Dialog<bool> {
+    let (title, message) = (title.into(), message.into());
+    open_dialog::<DS, _, _>(ModalOptions::default(), move |cx| {
+        let (ok, cancel) = (cx.clone(), cx.clone());
+        let buttons = h_stack((
+            cx.focusable(button::<DS>(
+                || "Cancel".to_string(),
+                ButtonOptions::default().variant(ButtonVariant::Secondary),
+                move || cancel.choose(false),
+            )),
+            cx.focusable(button::<DS>(
+                || "OK".to_string(),
+                ButtonOptions::default(),
+                move || ok.choose(true),
+            )),
+        ))
+        .style(|s| s.justify_end().gap(DS::PADDING, 0.));
+        message_body::<DS>(title, message, buttons)
+    })
+}
+
+/// Show a message with a single OK button.
+pub fn alert<DS: DesignSystem + 'static>(
+    title: impl Into<String>,
+    message: impl Into<String>,
+) -> Dialog<()> {
+    let (title, message) = (title.into(), message.into());
+    open_dialog::<DS, _, _>(
+        ModalOptions {
+            close_on_backdrop: true,
+            ..Default::default()
+        },
+        move |cx| {
+            let ok = cx.clone();
+            let buttons = h_stack((cx.focusable(button::<DS>(
+                || "OK".to_string(),
+                ButtonOptions::default(),
+                move || ok.choose(()),
+            )),))
+            .style(|s| s.justify_end());
+            message_body::<DS>(title, message, buttons)
+        },
+    )
+}
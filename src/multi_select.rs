@@ -0,0 +1,205 @@
+use std::hash::Hash;
+
+use floem::{
+    reactive::{create_rw_signal, ReadSignal, RwSignal},
+    style::{Background, BorderColor, Display, TextColor},
+    view::View,
+    views::{container, empty, h_stack, label, list, scroll, v_stack, Decorators},
+};
+
+use crate::{
+    click_outside::ExtClickOutside,
+    dropdown::{arrow_and_container, ArrowSelect, DPStyles, DropDownStyles},
+    icons::{icon_circle_check, icon_circle_check_solid, icon_x_mark},
+    pop_over::{pop_over, Align, Placement, Side},
+    style::{DesignSystem, ExtDynamicColor},
+};
+
+/// A dropdown for picking several items. `selected` holds the keys of the picked items in the
+/// order they were picked. The main box shows up to `max_chips` of them as removable chips and
+/// a "+N more" count for the rest.
+pub fn multi_select<DS, T, K>(
+    items: ReadSignal<im::Vector<T>>,
+    key: impl Fn(&T) -> K + Copy + 'static,
+    label_fn: impl Fn(&T) -> String + Copy + 'static,
+    selected: RwSignal<im::Vector<K>>,
+    max_chips: usize,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    T: Clone + 'static,
+    K: Clone + Hash + Eq + 'static,
+{
+    let display_scroll = create_rw_signal(false);
+    let (box_func, box_size) = crate::style::lazy_size();
+
+    let is_selected = move |k: &K| selected.with(|selected| selected.contains(k));
+    let toggle = move |k: K| {
+        selected.update(|selected| match selected.index_of(&k) {
+            Some(idx) => {
+                selected.remove(idx);
+            }
+            None => selected.push_back(k),
+        })
+    };
+    let label_of = move |k: &K| {
+        items.with(|list| {
+            list.iter()
+                .find(|item| &key(item) == k)
+                .map(label_fn)
+                .unwrap_or_default()
+        })
+    };
+
+    let chips = list(
+        move || selected.get().into_iter().take(max_chips),
+        |k| k.clone(),
+        move |k| {
+            let text = label_of(&k);
+            h_stack((
+                label(move || text.clone()),
+                icon_x_mark()
+                    .on_click_stop(move |_| toggle(k.clone()))
+                    .style(|s| {
+                        s.size(DS::BASE_FONT_SIZE * 0.7, DS::BASE_FONT_SIZE * 0.7)
+                            .dynamic_color(TextColor, DS::TEXT)
+                    }),
+            ))
+            .style(move |s| {
+                let sel_cont = styles.with(|val| val.sel_cont.clone());
+                s.items_center()
+                    .gap(DS::PADDING / 2., 0.)
+                    .padding_horiz(DS::PADDING / 2.)
+                    .border_radius(DS::BORDER_RADIUS)
+                    .ld_color(Background, sel_cont.base)
+            })
+        },
+    )
+    .style(|s| s.gap(DS::PADDING / 2., 0.).items_center());
+
+    let more = label(move || {
+        let hidden = selected.with(|selected| selected.len().saturating_sub(max_chips));
+        format!("+{hidden} more")
+    })
+    .style(move |s| {
+        s.padding_horiz(DS::PADDING / 2.)
+            .apply_if(selected.with(|selected| selected.len() <= max_chips), |s| {
+                s.display(Display::None)
+            })
+    });
+
+    let main_box = h_stack((
+        h_stack((chips, more)).style(|s| s.flex_grow(1.).items_center().padding_left(2.)),
+        arrow_and_container::<DS>(ArrowSelect::Right, styles, move || {
+            display_scroll.update(|val| *val = !*val);
+        }),
+    ))
+    .on_resize(box_func)
+    .style(move |s| {
+        let styles = styles.get();
+        s.items_center()
+            .justify_between()
+            .size_full()
+            .border(DS::BORDER_SMALL)
+            .border_radius(DS::BORDER_RADIUS)
+            .ld_color(BorderColor, styles.main_box_border)
+            .ld_color(Background, styles.main_box_back)
+    });
+
+    let drop_box = move || {
+        let action = move |text: &'static str, on_click: Box<dyn Fn()>| {
+            label(move || text.to_string())
+                .on_click_stop(move |_| on_click())
+                .style(move |s| {
+                    s.padding_horiz(DS::PADDING / 2.)
+                        .border_radius(DS::BORDER_RADIUS)
+                        .dynamic_color(
+                            Background,
+                            styles.with(|val| val.sel_cont.clone().disable_base()),
+                        )
+                })
+        };
+        let actions = h_stack((
+            action(
+                "Select all",
+                Box::new(move || {
+                    let all = items.with(|list| list.iter().map(key).collect());
+                    selected.set(all);
+                }),
+            ),
+            empty().style(|s| s.flex_grow(1.)),
+            action("Clear", Box::new(move || selected.set(im::Vector::new()))),
+        ))
+        .style(move |s| {
+            s.width_full()
+                .padding(DPStyles::SCROLL_CONTAINER_PADDING)
+                .border_bottom(DS::BORDER_SMALL)
+                .ld_color(BorderColor, styles.with(|val| val.drop_box_border))
+        });
+
+        let rows = scroll(
+            list(
+                move || items.get().into_iter().enumerate(),
+                move |(idx, item)| (*idx, key(item)),
+                move |(_, item)| {
+                    let k = key(&item);
+                    let check_key = k.clone();
+                    let text = label_fn(&item);
+                    let icon_size = DS::BASE_FONT_SIZE;
+                    h_stack((
+                        container(icon_circle_check_solid()).style(move |s| {
+                            s.size(icon_size, icon_size)
+                                .apply_if(!is_selected(&check_key), |s| s.display(Display::None))
+                        }),
+                        container(icon_circle_check()).style({
+                            let check_key = k.clone();
+                            move |s| {
+                                s.size(icon_size, icon_size)
+                                    .apply_if(is_selected(&check_key), |s| s.display(Display::None))
+                            }
+                        }),
+                        label(move || text.clone()),
+                    ))
+                    // rows toggle and keep the list open
+                    .on_click_stop(move |_| toggle(k.clone()))
+                    .style(move |s| {
+                        s.width_full()
+                            .items_center()
+                            .gap(DS::PADDING / 2., 0.)
+                            .padding_horiz(DPStyles::SCROLL_CONTAINER_PADDING)
+                            .dynamic_color(
+                                Background,
+                                styles.with(|val| val.sel_cont.clone().disable_base()),
+                            )
+                    })
+                },
+            )
+            .style(|s| s.flex_col().width_full()),
+        )
+        .style(move |s| {
+            let styles = styles.get();
+            s.width_full()
+                .apply_opt(styles.scroll_height, |s, val| s.height(val))
+                .apply_opt(styles.max_scroll_height, |s, val| s.max_height(val))
+        });
+
+        v_stack((actions, rows)).style(move |s| {
+            let styles = styles.get();
+            s.min_width(box_size().width)
+                .border(DS::BORDER_NORMAL)
+                .border_radius(DS::BORDER_RADIUS)
+                .ld_color(BorderColor, styles.drop_box_border)
+                .ld_color(Background, styles.drop_box_back)
+        })
+    };
+
+    pop_over(
+        main_box,
+        drop_box,
+        display_scroll,
+        Placement::new(Side::Bottom, Align::Start).offset(3.),
+    )
+    .on_click_outside(move || display_scroll.set(false))
+    .style(|s| s.size_full())
+}
@@ -1,89 +1,376 @@
-use floem::glazier::kurbo::Rect;
-use floem::id::Id;
-use floem::taffy;
-use floem::view::{ChangeFlags, View};
-use floem::ViewContext;
-use floem::{context::*, Renderer};
-
-pub struct PopOver<PV: View, CV: View> {
-    id: Id,
-    parent: PV,
-    popover: CV,
+use std::rc::Rc;
+
+use floem::{
+    action::{add_overlay, remove_overlay},
+    id::Id,
+    kurbo::{Point, Rect, Size},
+    reactive::{create_effect, create_rw_signal, RwSignal},
+    view::View,
+    views::{container, Decorators},
+};
+
+use crate::click_outside::ExtClickOutside;
+
+/// Where a popover waits until it has been measured, out of sight and out of reach of the pointer.
+const UNMEASURED_ORIGIN: Point = Point::new(-100_000., -100_000.);
+
+/// The side of the anchor the popover is placed on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+impl Side {
+    pub const fn opposite(self) -> Self {
+        match self {
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    const fn is_vertical(self) -> bool {
+        matches!(self, Side::Top | Side::Bottom)
+    }
 }
 
-pub fn pop_over<PV: View, CV: View>(
-    parent: impl FnOnce() -> PV,
-    popover: impl FnOnce() -> CV,
-) -> PopOver<PV, CV> {
-    let cx = ViewContext::get_current();
-    let id = cx.new_id();
-    let mut child_cx = cx;
-    child_cx.id = id;
-    ViewContext::save();
-    ViewContext::set_current(child_cx);
-    let parent = parent();
-    let child = popover();
-    ViewContext::restore();
-
-    PopOver {
-        id,
-        parent,
-        popover: child,
+/// How the popover lines up with the anchor along the chosen side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Placement {
+    pub side: Side,
+    pub align: Align,
+    /// Gap between the anchor and the popover
+    pub offset: f64,
+    /// Minimum distance kept from the window edges
+    pub margin: f64,
+    /// Move to the opposite side when the chosen one doesn't have room
+    pub flip: bool,
+    /// Slide along the side to stay inside the window
+    pub shift: bool,
+}
+impl Default for Placement {
+    fn default() -> Self {
+        Self::new(Side::Bottom, Align::Start)
     }
 }
+impl Placement {
+    pub const fn new(side: Side, align: Align) -> Self {
+        Self {
+            side,
+            align,
+            offset: 4.,
+            margin: 4.,
+            flip: true,
+            shift: true,
+        }
+    }
 
-impl<PV: View, CV: View> View for PopOver<PV, CV> {
-    fn id(&self) -> Id {
-        self.id
+    pub const fn offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
     }
 
-    fn child(&mut self, id: Id) -> Option<&mut dyn View> {
-        if self.popover.id() == id {
-            Some(&mut self.popover)
-        } else if self.parent.id() == id {
-            Some(&mut self.parent)
-        } else {
-            None
+    fn origin_on(&self, side: Side, anchor: Rect, content: Size) -> Point {
+        let cross = |start: f64, end: f64, len: f64| match self.align {
+            Align::Start => start,
+            Align::Center => (start + end - len) / 2.,
+            Align::End => end - len,
+        };
+        match side {
+            Side::Top => Point::new(
+                cross(anchor.x0, anchor.x1, content.width),
+                anchor.y0 - self.offset - content.height,
+            ),
+            Side::Bottom => Point::new(
+                cross(anchor.x0, anchor.x1, content.width),
+                anchor.y1 + self.offset,
+            ),
+            Side::Left => Point::new(
+                anchor.x0 - self.offset - content.width,
+                cross(anchor.y0, anchor.y1, content.height),
+            ),
+            Side::Right => Point::new(
+                anchor.x1 + self.offset,
+                cross(anchor.y0, anchor.y1, content.height),
+            ),
         }
     }
 
-    fn children(&mut self) -> Vec<&mut dyn View> {
-        vec![&mut self.parent, &mut self.popover]
+    /// How far a popover on `side` spills past the window edges on the main axis.
+    fn overflow(&self, side: Side, origin: Point, content: Size, window: Size) -> f64 {
+        let (start, len, max) = if side.is_vertical() {
+            (origin.y, content.height, window.height)
+        } else {
+            (origin.x, content.width, window.width)
+        };
+        (self.margin - start).max(0.) + (start + len - (max - self.margin)).max(0.)
     }
 
-    fn debug_name(&self) -> std::borrow::Cow<'static, str> {
-        "Container".into()
+    /// Where to put a popover of size `content` next to `anchor`, all in window coordinates.
+    /// Returns the popover origin and the side it ended up on after flipping.
+    pub fn place(&self, anchor: Rect, content: Size, window: Size) -> (Point, Side) {
+        let mut side = self.side;
+        let mut origin = self.origin_on(side, anchor, content);
+        if self.flip {
+            let overflow = self.overflow(side, origin, content, window);
+            if overflow > 0. {
+                let flipped = self.origin_on(side.opposite(), anchor, content);
+                if self.overflow(side.opposite(), flipped, content, window) < overflow {
+                    side = side.opposite();
+                    origin = flipped;
+                }
+            }
+        }
+        if self.shift {
+            let clamp = |value: f64, len: f64, max: f64| {
+                value.min(max - self.margin - len).max(self.margin)
+            };
+            if side.is_vertical() {
+                origin.x = clamp(origin.x, content.width, window.width);
+            } else {
+                origin.y = clamp(origin.y, content.height, window.height);
+            }
+        }
+        (origin, side)
     }
+}
 
-    fn update(
-        &mut self,
-        _cx: &mut floem::context::UpdateCx,
-        _state: Box<dyn std::any::Any>,
-    ) -> floem::view::ChangeFlags {
-        ChangeFlags::empty()
+/// The live geometry of an open popover.
+#[derive(Clone, Copy)]
+pub struct PopOverState {
+    pub anchor: RwSignal<Rect>,
+    pub content: RwSignal<Size>,
+    pub window: RwSignal<Size>,
+    /// Where the popover is drawn, in window coordinates. Far outside the window until the
+    /// popover and the window have been measured.
+    pub origin: RwSignal<Point>,
+    /// The side the popover is currently shown on, after flipping
+    pub side: RwSignal<Side>,
+    overlay: RwSignal<Option<Id>>,
+}
+impl PopOverState {
+    /// Take the popover out of the window right away, regardless of its visibility signal.
+    pub fn remove(&self) {
+        if let Some(id) = self.overlay.get_untracked() {
+            remove_overlay(id);
+            self.overlay.set(None);
+        }
     }
+}
+
+/// Anchor `popover` to a rectangle in window coordinates rather than to a view, e.g. a zero sized
+/// rectangle under the pointer for context menus. `owner` is the view clicks inside the popover
+/// are attributed to. The caller is responsible for calling [`PopOverState::remove`] when the
+/// owner goes away.
+pub fn pop_over_rect<CV>(
+    anchor: RwSignal<Rect>,
+    popover: impl Fn() -> CV + 'static,
+    visible: RwSignal<bool>,
+    placement: Placement,
+    owner: Id,
+) -> PopOverState
+where
+    CV: View + 'static,
+{
+    let state = PopOverState {
+        anchor,
+        content: create_rw_signal(Size::ZERO),
+        window: create_rw_signal(Size::ZERO),
+        origin: create_rw_signal(UNMEASURED_ORIGIN),
+        side: create_rw_signal(placement.side),
+        overlay: create_rw_signal(None),
+    };
+    create_effect(move |_| {
+        let (content, window) = (state.content.get(), state.window.get());
+        // before the first layout any placement would be wrong and the popover would jump
+        if content == Size::ZERO || window == Size::ZERO {
+            state.origin.set(UNMEASURED_ORIGIN);
+            return;
+        }
+        let (origin, side) = placement.place(state.anchor.get(), content, window);
+        state.origin.set(origin);
+        state.side.set(side);
+    });
+
+    let popover = Rc::new(popover);
+    create_effect(move |_| {
+        let show = visible.get();
+        match (show, state.overlay.get_untracked()) {
+            (true, None) => {
+                let popover = popover.clone();
+                let id = add_overlay(Point::ZERO, move |_| overlay_layer(popover(), owner, state));
+                state.overlay.set(Some(id));
+            }
+            (false, Some(_)) => state.remove(),
+            _ => {}
+        }
+    });
+    state
+}
+
+/// Anchor `popover` to `parent`. While `visible` is true the popover is drawn in a window
+/// overlay above everything else, positioned by `placement`, and follows the anchor when it moves
+/// or resizes. Clicks inside the popover count as inside `parent` for
+/// [`ExtClickOutside`](crate::click_outside::ExtClickOutside).
+pub fn pop_over<PV, CV>(
+    parent: PV,
+    popover: impl Fn() -> CV + 'static,
+    visible: RwSignal<bool>,
+    placement: Placement,
+) -> impl View
+where
+    PV: View + 'static,
+    CV: View + 'static,
+{
+    let anchor_rect = create_rw_signal(Rect::ZERO);
+    let anchor_origin = create_rw_signal(Point::ZERO);
+    let anchor_size = create_rw_signal(Size::ZERO);
+    create_effect(move |_| {
+        let rect = Rect::from_origin_size(anchor_origin.get(), anchor_size.get());
+        anchor_rect.set(rect);
+    });
 
-    fn layout(&mut self, cx: &mut floem::context::LayoutCx) -> taffy::prelude::Node {
-        cx.layout_node(self.id, true, |cx| {
-            vec![self.parent.layout_main(cx), self.popover.layout_main(cx)]
-        })
+    let anchor = container(parent);
+    let state = pop_over_rect(anchor_rect, popover, visible, placement, anchor.id());
+
+    anchor
+        .on_move(move |point| anchor_origin.set(point))
+        .on_resize(move |rect| anchor_size.set(rect.size()))
+        .on_cleanup(move || state.remove())
+}
+
+/// A window sized layer that positions `content` absolutely. The layer itself has no handlers,
+/// so pointer events outside the popover fall through to the views below.
+fn overlay_layer<CV: View + 'static>(content: CV, owner: Id, state: PopOverState) -> impl View {
+    container(
+        container(content)
+            .click_outside_owner(owner)
+            .on_resize(move |rect| state.content.set(rect.size()))
+            .style(move |s| {
+                let origin = state.origin.get();
+                s.absolute().inset_left(origin.x).inset_top(origin.y)
+            }),
+    )
+    .on_resize(move |rect| state.window.set(rect.size()))
+    .style(|s| s.size_full())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Size = Size::new(400., 300.);
+    const CONTENT: Size = Size::new(60., 30.);
+
+    #[test]
+    fn places_on_each_side_and_alignment() {
+        let anchor = Rect::new(100., 100., 140., 120.);
+        let cases = [
+            (Side::Bottom, Align::Start, (100., 124.)),
+            (Side::Bottom, Align::Center, (90., 124.)),
+            (Side::Bottom, Align::End, (80., 124.)),
+            (Side::Top, Align::Start, (100., 66.)),
+            (Side::Top, Align::Center, (90., 66.)),
+            (Side::Top, Align::End, (80., 66.)),
+            (Side::Left, Align::Start, (36., 100.)),
+            (Side::Left, Align::Center, (36., 95.)),
+            (Side::Left, Align::End, (36., 90.)),
+            (Side::Right, Align::Start, (144., 100.)),
+            (Side::Right, Align::Center, (144., 95.)),
+            (Side::Right, Align::End, (144., 90.)),
+        ];
+        for (side, align, (x, y)) in cases {
+            let placed = Placement::new(side, align).place(anchor, CONTENT, WINDOW);
+            assert_eq!(placed, (Point::new(x, y), side), "{side:?} {align:?}");
+        }
     }
 
-    fn compute_layout(&mut self, cx: &mut floem::context::LayoutCx) -> Option<Rect> {
-        Some(self.popover.compute_layout_main(cx))
+    #[test]
+    fn flips_to_the_side_with_room() {
+        let cases = [
+            (
+                Side::Bottom,
+                Rect::new(100., 270., 140., 290.),
+                (100., 236.),
+            ),
+            (Side::Top, Rect::new(100., 10., 140., 30.), (100., 34.)),
+            (Side::Right, Rect::new(350., 100., 390., 120.), (286., 100.)),
+            (Side::Left, Rect::new(10., 100., 50., 120.), (54., 100.)),
+        ];
+        for (side, anchor, (x, y)) in cases {
+            let placement = Placement::new(side, Align::Start);
+            assert_eq!(
+                placement.place(anchor, CONTENT, WINDOW),
+                (Point::new(x, y), side.opposite()),
+                "{side:?}"
+            );
+            let fixed = Placement {
+                flip: false,
+                ..placement
+            };
+            assert_eq!(fixed.place(anchor, CONTENT, WINDOW).1, side, "{side:?}");
+        }
     }
 
-    fn event(
-        &mut self,
-        cx: &mut floem::context::EventCx,
-        id_path: Option<&[Id]>,
-        event: floem::event::Event,
-    ) -> bool {
-        false
+    #[test]
+    fn keeps_side_when_flipping_does_not_help() {
+        let window = Size::new(400., 60.);
+        let anchor = Rect::new(100., 20., 140., 40.);
+        let placed = Placement::new(Side::Bottom, Align::Start).place(anchor, CONTENT, window);
+        assert_eq!(placed, (Point::new(100., 44.), Side::Bottom));
     }
 
-    fn paint(&mut self, cx: &mut PaintCx) {
-        // cx.fill();
-        self.child.paint_main(cx);
+    #[test]
+    fn shifts_along_the_side_to_stay_inside() {
+        let cases = [
+            (
+                Side::Bottom,
+                Align::Start,
+                Rect::new(370., 100., 390., 120.),
+                (336., 124.),
+            ),
+            (
+                Side::Top,
+                Align::End,
+                Rect::new(0., 100., 20., 120.),
+                (4., 66.),
+            ),
+            (
+                Side::Right,
+                Align::Start,
+                Rect::new(100., 285., 140., 295.),
+                (144., 266.),
+            ),
+            (
+                Side::Left,
+                Align::End,
+                Rect::new(100., 0., 140., 10.),
+                (36., 4.),
+            ),
+        ];
+        for (side, align, anchor, (x, y)) in cases {
+            let placement = Placement::new(side, align);
+            assert_eq!(
+                placement.place(anchor, CONTENT, WINDOW),
+                (Point::new(x, y), side),
+                "{side:?} {align:?}"
+            );
+            let unshifted = Placement {
+                shift: false,
+                ..placement
+            };
+            let expected = placement.origin_on(side, anchor, CONTENT);
+            assert_eq!(unshifted.place(anchor, CONTENT, WINDOW).0, expected);
+        }
     }
 }
@@ -1 +1,217 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
 
+use floem::{
+    action::exec_after,
+    event::{Event, EventListener},
+    id::Id,
+    keyboard::{Key, NamedKey},
+    reactive::{create_effect, RwSignal},
+    view::View,
+    views::{container, Decorators},
+    EventPropagation,
+};
+
+use crate::{
+    click_outside::ExtClickOutside,
+    pop_over::{pop_over, Placement},
+};
+
+/// Identifies a popover registered with the popover stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PopoverKey(u64);
+
+struct Entry {
+    key: PopoverKey,
+    parent: Option<PopoverKey>,
+    visible: RwSignal<bool>,
+    /// Gets keyboard focus back when the popover closes
+    focus_return: Option<Id>,
+}
+
+#[derive(Default)]
+struct PopoverStack {
+    /// Every registered popover
+    registered: Vec<Entry>,
+    /// Open popovers, topmost last
+    open: Vec<PopoverKey>,
+    next_key: u64,
+}
+
+thread_local! {
+    static STACK: RefCell<PopoverStack> = RefCell::new(PopoverStack::default());
+}
+
+/// Register a popover whose visibility is driven by `visible`. Opening it pushes it on the
+/// stack; closing it also closes every popover opened as its descendant and moves focus back to
+/// `focus_return`. Nested menus pass the key of the menu they open from as `parent`.
+pub fn register_popover(
+    visible: RwSignal<bool>,
+    parent: Option<PopoverKey>,
+    focus_return: Option<Id>,
+) -> PopoverKey {
+    let key = STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let key = PopoverKey(stack.next_key);
+        stack.next_key += 1;
+        stack.registered.push(Entry {
+            key,
+            parent,
+            visible,
+            focus_return,
+        });
+        key
+    });
+
+    create_effect(move |_| {
+        if visible.get() {
+            STACK.with(|stack| {
+                let mut stack = stack.borrow_mut();
+                if !stack.open.contains(&key) {
+                    stack.open.push(key);
+                }
+            });
+        } else {
+            on_closed(key);
+        }
+    });
+    key
+}
+
+fn on_closed(key: PopoverKey) {
+    let (children, focus_return) = STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let was_open = stack.open.contains(&key);
+        stack.open.retain(|open| *open != key);
+        let children: Vec<RwSignal<bool>> = stack
+            .registered
+            .iter()
+            .filter(|entry| entry.parent == Some(key))
+            .map(|entry| entry.visible)
+            .collect();
+        let focus_return = stack
+            .registered
+            .iter()
+            .find(|entry| entry.key == key)
+            .and_then(|entry| entry.focus_return)
+            .filter(|_| was_open);
+        (children, focus_return)
+    });
+    // closing the children runs their effects, which need the stack
+    for child in children {
+        if child.get_untracked() {
+            child.set(false);
+        }
+    }
+    if let Some(id) = focus_return {
+        id.request_focus();
+    }
+}
+
+/// Remove a popover from the stack for good, e.g. when its view is cleaned up.
+pub fn unregister_popover(key: PopoverKey) {
+    STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        stack.open.retain(|open| *open != key);
+        stack.registered.retain(|entry| entry.key != key);
+    });
+}
+
+fn visible_of(key: PopoverKey) -> Option<RwSignal<bool>> {
+    STACK.with(|stack| {
+        stack
+            .borrow()
+            .registered
+            .iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| entry.visible)
+    })
+}
+
+pub fn top_popover() -> Option<PopoverKey> {
+    STACK.with(|stack| stack.borrow().open.last().copied())
+}
+
+pub fn is_top_popover(key: PopoverKey) -> bool {
+    top_popover() == Some(key)
+}
+
+pub fn close_popover(key: PopoverKey) {
+    if let Some(visible) = visible_of(key) {
+        visible.set(false);
+    }
+}
+
+/// Close the topmost popover. Returns false if nothing was open.
+pub fn close_top_popover() -> bool {
+    match top_popover() {
+        Some(key) => {
+            close_popover(key);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn close_all_popovers() {
+    let open = STACK.with(|stack| stack.borrow().open.clone());
+    for key in open.into_iter().rev() {
+        close_popover(key);
+    }
+}
+
+/// A [`pop_over`] that takes part in the popover stack. The popover takes keyboard focus when it
+/// opens, Escape and clicks outside close it only if it is the topmost popover, and focus returns
+/// to the trigger when it closes.
+pub fn managed_pop_over<PV, CV>(
+    trigger: PV,
+    popover: impl Fn() -> CV + 'static,
+    visible: RwSignal<bool>,
+    placement: Placement,
+    parent: Option<PopoverKey>,
+) -> (impl View, PopoverKey)
+where
+    PV: View + 'static,
+    CV: View + 'static,
+{
+    let trigger_id = trigger.id();
+    let key = register_popover(visible, parent, Some(trigger_id));
+
+    let content = move || {
+        // Focus once the overlay has been added to the window. The timer is scheduled before the
+        // content is built so content that focuses one of its own children wins.
+        let focus = Rc::new(Cell::new(None::<Id>));
+        let focus_id = focus.clone();
+        exec_after(Duration::ZERO, move |_| {
+            if let Some(id) = focus_id.get() {
+                id.request_focus();
+            }
+        });
+        let view = container(popover()).keyboard_navigatable().on_event(
+            EventListener::KeyDown,
+            move |event| match event {
+                Event::KeyDown(key_event)
+                    if key_event.key.logical_key == Key::Named(NamedKey::Escape) =>
+                {
+                    close_top_popover();
+                    EventPropagation::Stop
+                }
+                _ => EventPropagation::Continue,
+            },
+        );
+        focus.set(Some(view.id()));
+        view
+    };
+
+    let view = pop_over(trigger, content, visible, placement)
+        .on_click_outside(move || {
+            if visible.get_untracked() && is_top_popover(key) {
+                visible.set(false);
+            }
+        })
+        .on_cleanup(move || unregister_popover(key));
+    (view, key)
+}
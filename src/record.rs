@@ -0,0 +1,988 @@
+//! Recording of input events and their replay against event handlers, so an interactive session
+//! with a component can be saved to a file and turned into a regression test.
+//!
+//! floem only dispatches events inside a window, so whole components are replayed against a
+//! [`HeadlessTree`]: the bounds of their views together with the [`EventHandlers`] the views
+//! install, which routes the rebuilt events the way a window would.
+//!
+//! Sessions are stored one event per line as `<millis> <kind> <fields..>`:
+//!
+//! ```text
+//! 0 pointer_down 12.5 8 primary 1 -
+//! 16 pointer_move 14 8 -
+//! 80 pointer_up 14 8 primary 1 -
+//! 120 wheel 14 8 0 -40 -
+//! 300 key_down Ctrl+K
+//! 900 resize 800 600
+//! ```
+
+use std::{
+    cell::RefCell,
+    fmt,
+    path::Path,
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use floem::{
+    event::{Event, EventListener},
+    keyboard::ModifiersState,
+    kurbo::{Point, Rect, Size, Vec2},
+    pointer::{PointerButton, PointerInputEvent, PointerMoveEvent, PointerWheelEvent},
+    views::Decorators,
+    EventPropagation,
+};
+
+use crate::{
+    handlers::VirtualClock,
+    keymap::{KeyPress, Keymap, KeymapOutcome, KeymapScope, Modifiers, ParseKeyBindingError},
+    style::EventHandlers,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordedButton {
+    Primary,
+    Secondary,
+    Auxiliary,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordedInput {
+    PointerDown {
+        pos: Point,
+        button: RecordedButton,
+        count: u8,
+        mods: Modifiers,
+    },
+    PointerUp {
+        pos: Point,
+        button: RecordedButton,
+        count: u8,
+        mods: Modifiers,
+    },
+    PointerMove {
+        pos: Point,
+        mods: Modifiers,
+    },
+    Wheel {
+        pos: Point,
+        delta: Vec2,
+        mods: Modifiers,
+    },
+    KeyDown(KeyPress),
+    KeyUp(KeyPress),
+    Resize(Size),
+}
+impl RecordedInput {
+    /// Capture a floem event. Returns `None` for events that aren't recorded.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let button = |button: &PointerButton| match button {
+            PointerButton::Secondary => RecordedButton::Secondary,
+            PointerButton::Auxiliary => RecordedButton::Auxiliary,
+            _ => RecordedButton::Primary,
+        };
+        Some(match event {
+            Event::PointerDown(e) => Self::PointerDown {
+                pos: e.pos,
+                button: button(&e.button),
+                count: e.count,
+                mods: e.modifiers.into(),
+            },
+            Event::PointerUp(e) => Self::PointerUp {
+                pos: e.pos,
+                button: button(&e.button),
+                count: e.count,
+                mods: e.modifiers.into(),
+            },
+            Event::PointerMove(e) => Self::PointerMove {
+                pos: e.pos,
+                mods: e.modifiers.into(),
+            },
+            Event::PointerWheel(e) => Self::Wheel {
+                pos: e.pos,
+                delta: e.delta,
+                mods: e.modifiers.into(),
+            },
+            Event::KeyDown(_) => Self::KeyDown(KeyPress::from_event(event)?),
+            Event::KeyUp(e) => {
+                Self::KeyUp(KeyPress::from_key(&e.key.logical_key, e.modifiers.into())?)
+            }
+            Event::WindowResized(size) => Self::Resize(*size),
+            _ => return None,
+        })
+    }
+
+    pub fn listener(&self) -> EventListener {
+        match self {
+            Self::PointerDown { .. } => EventListener::PointerDown,
+            Self::PointerUp { .. } => EventListener::PointerUp,
+            Self::PointerMove { .. } => EventListener::PointerMove,
+            Self::Wheel { .. } => EventListener::PointerWheel,
+            Self::KeyDown(_) => EventListener::KeyDown,
+            Self::KeyUp(_) => EventListener::KeyUp,
+            Self::Resize(_) => EventListener::WindowResized,
+        }
+    }
+
+    /// Rebuild the floem event. Key events can't be constructed outside of the windowing
+    /// backend, so they return `None` and are replayed as key presses instead, see
+    /// [`EventHandlers::on_key_press`] and [`KeymapTarget`].
+    pub fn to_event(&self) -> Option<Event> {
+        let button = |button: RecordedButton| match button {
+            RecordedButton::Primary => PointerButton::Primary,
+            RecordedButton::Secondary => PointerButton::Secondary,
+            RecordedButton::Auxiliary => PointerButton::Auxiliary,
+        };
+        Some(match *self {
+            Self::PointerDown {
+                pos,
+                button: b,
+                count,
+                mods,
+            } => Event::PointerDown(PointerInputEvent {
+                pos,
+                button: button(b),
+                count,
+                modifiers: modifiers_state(mods),
+            }),
+            Self::PointerUp {
+                pos,
+                button: b,
+                count,
+                mods,
+            } => Event::PointerUp(PointerInputEvent {
+                pos,
+                button: button(b),
+                count,
+                modifiers: modifiers_state(mods),
+            }),
+            Self::PointerMove { pos, mods } => Event::PointerMove(PointerMoveEvent {
+                pos,
+                modifiers: modifiers_state(mods),
+            }),
+            Self::Wheel { pos, delta, mods } => Event::PointerWheel(PointerWheelEvent {
+                pos,
+                delta,
+                modifiers: modifiers_state(mods),
+            }),
+            Self::Resize(size) => Event::WindowResized(size),
+            Self::KeyDown(_) | Self::KeyUp(_) => return None,
+        })
+    }
+}
+
+fn modifiers_state(mods: Modifiers) -> ModifiersState {
+    let mut state = ModifiersState::empty();
+    state.set(ModifiersState::CONTROL, mods.ctrl);
+    state.set(ModifiersState::SHIFT, mods.shift);
+    state.set(ModifiersState::ALT, mods.alt);
+    state.set(ModifiersState::SUPER, mods.meta);
+    state
+}
+
+fn write_mods(f: &mut fmt::Formatter<'_>, mods: Modifiers) -> fmt::Result {
+    let names: Vec<&str> = [
+        (mods.ctrl, "ctrl"),
+        (mods.shift, "shift"),
+        (mods.alt, "alt"),
+        (mods.meta, "meta"),
+    ]
+    .into_iter()
+    .filter_map(|(held, name)| held.then_some(name))
+    .collect();
+    if names.is_empty() {
+        write!(f, "-")
+    } else {
+        write!(f, "{}", names.join("+"))
+    }
+}
+
+fn parse_mods(token: &str) -> Option<Modifiers> {
+    let mut mods = Modifiers::default();
+    if token == "-" {
+        return Some(mods);
+    }
+    for name in token.split('+') {
+        match name {
+            "ctrl" => mods.ctrl = true,
+            "shift" => mods.shift = true,
+            "alt" => mods.alt = true,
+            "meta" => mods.meta = true,
+            _ => return None,
+        }
+    }
+    Some(mods)
+}
+
+impl fmt::Display for RecordedButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Primary => write!(f, "primary"),
+            Self::Secondary => write!(f, "secondary"),
+            Self::Auxiliary => write!(f, "auxiliary"),
+        }
+    }
+}
+impl FromStr for RecordedButton {
+    type Err = ParseEventError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "primary" => Ok(Self::Primary),
+            "secondary" => Ok(Self::Secondary),
+            "auxiliary" => Ok(Self::Auxiliary),
+            other => Err(ParseEventError::UnknownButton(other.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedEvent {
+    /// Time since the recording started
+    pub at: Duration,
+    pub input: RecordedInput,
+}
+impl fmt::Display for RecordedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.at.as_millis())?;
+        match &self.input {
+            RecordedInput::PointerDown {
+                pos,
+                button,
+                count,
+                mods,
+            }
+            | RecordedInput::PointerUp {
+                pos,
+                button,
+                count,
+                mods,
+            } => {
+                let kind = match self.input {
+                    RecordedInput::PointerDown { .. } => "pointer_down",
+                    _ => "pointer_up",
+                };
+                write!(f, "{kind} {} {} {button} {count} ", pos.x, pos.y)?;
+                write_mods(f, *mods)
+            }
+            RecordedInput::PointerMove { pos, mods } => {
+                write!(f, "pointer_move {} {} ", pos.x, pos.y)?;
+                write_mods(f, *mods)
+            }
+            RecordedInput::Wheel { pos, delta, mods } => {
+                write!(f, "wheel {} {} {} {} ", pos.x, pos.y, delta.x, delta.y)?;
+                write_mods(f, *mods)
+            }
+            RecordedInput::KeyDown(press) => write!(f, "key_down {press}"),
+            RecordedInput::KeyUp(press) => write!(f, "key_up {press}"),
+            RecordedInput::Resize(size) => write!(f, "resize {} {}", size.width, size.height),
+        }
+    }
+}
+
+/// Why a line of a recording isn't a valid event. Fields are counted from 0, starting with the
+/// timestamp.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseEventError {
+    MissingField(usize),
+    InvalidField(usize),
+    UnknownKind(String),
+    UnknownButton(String),
+    InvalidKey(ParseKeyBindingError),
+}
+impl fmt::Display for ParseEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField(idx) => write!(f, "missing field {idx}"),
+            Self::InvalidField(idx) => write!(f, "invalid field {idx}"),
+            Self::UnknownKind(kind) => write!(f, "unknown event `{kind}`"),
+            Self::UnknownButton(button) => write!(f, "unknown button `{button}`"),
+            Self::InvalidKey(err) => write!(f, "invalid key: {err}"),
+        }
+    }
+}
+impl std::error::Error for ParseEventError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseRecordingError {
+    /// 1-based line number
+    pub line: usize,
+    pub error: ParseEventError,
+}
+impl fmt::Display for ParseRecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid recorded event on line {}: {}",
+            self.line, self.error
+        )
+    }
+}
+impl std::error::Error for ParseRecordingError {}
+
+impl FromStr for RecordedEvent {
+    type Err = ParseEventError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let field = |idx: usize| {
+            fields
+                .get(idx)
+                .copied()
+                .ok_or(ParseEventError::MissingField(idx))
+        };
+        let number = |idx: usize| -> Result<f64, ParseEventError> {
+            field(idx)?
+                .parse()
+                .map_err(|_| ParseEventError::InvalidField(idx))
+        };
+        let point = |idx: usize| -> Result<Point, ParseEventError> {
+            Ok(Point::new(number(idx)?, number(idx + 1)?))
+        };
+        let mods = |idx: usize| parse_mods(field(idx)?).ok_or(ParseEventError::InvalidField(idx));
+        let key = |idx: usize| -> Result<KeyPress, ParseEventError> {
+            field(idx)?.parse().map_err(ParseEventError::InvalidKey)
+        };
+
+        let at = field(0)?
+            .parse()
+            .map_err(|_| ParseEventError::InvalidField(0))?;
+        let at = Duration::from_millis(at);
+        let input = match field(1)? {
+            kind @ ("pointer_down" | "pointer_up") => {
+                let pos = point(2)?;
+                let button = field(4)?.parse()?;
+                let count = field(5)?
+                    .parse()
+                    .map_err(|_| ParseEventError::InvalidField(5))?;
+                let mods = mods(6)?;
+                if kind == "pointer_down" {
+                    RecordedInput::PointerDown {
+                        pos,
+                        button,
+                        count,
+                        mods,
+                    }
+                } else {
+                    RecordedInput::PointerUp {
+                        pos,
+                        button,
+                        count,
+                        mods,
+                    }
+                }
+            }
+            "pointer_move" => RecordedInput::PointerMove {
+                pos: point(2)?,
+                mods: mods(4)?,
+            },
+            "wheel" => RecordedInput::Wheel {
+                pos: point(2)?,
+                delta: Vec2::new(number(4)?, number(5)?),
+                mods: mods(6)?,
+            },
+            "key_down" => RecordedInput::KeyDown(key(2)?),
+            "key_up" => RecordedInput::KeyUp(key(2)?),
+            "resize" => RecordedInput::Resize(Size::new(number(2)?, number(3)?)),
+            other => return Err(ParseEventError::UnknownKind(other.to_string())),
+        };
+        Ok(Self { at, input })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    pub events: Vec<RecordedEvent>,
+}
+impl Recording {
+    pub fn parse(source: &str) -> Result<Self, ParseRecordingError> {
+        let events = source
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(idx, line)| {
+                line.parse().map_err(|error| ParseRecordingError {
+                    line: idx + 1,
+                    error,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { events })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::parse(&source)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    /// Feed every event to `target` at its recorded time on a [`VirtualClock`], so debounced and
+    /// throttled handlers see the original pace without the replay having to wait. Calls still
+    /// scheduled after the last event, such as a trailing debounce, run before this returns.
+    ///
+    /// Panics if a virtual clock is already running on this thread.
+    pub fn replay(&self, target: &mut impl ReplayTarget) {
+        let clock = VirtualClock::start(Instant::now());
+        let start = clock.now();
+        for event in &self.events {
+            clock.advance_to(start + event.at);
+            target.replay(&event.input);
+        }
+        clock.run_pending();
+    }
+}
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{event}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Something recorded input can be replayed against. Returns whether the input was handled.
+pub trait ReplayTarget {
+    fn replay(&mut self, input: &RecordedInput) -> bool;
+}
+/// Key downs and ups go to the key press handlers, everything else to the handler of its
+/// listener.
+impl ReplayTarget for EventHandlers {
+    fn replay(&mut self, input: &RecordedInput) -> bool {
+        if let RecordedInput::KeyDown(press) | RecordedInput::KeyUp(press) = input {
+            return self
+                .key_presses
+                .get(&input.listener())
+                .is_some_and(|handler| handler(press));
+        }
+        let (Some(handler), Some(event)) = (self.handlers.get(&input.listener()), input.to_event())
+        else {
+            return false;
+        };
+        handler(&event)
+    }
+}
+/// Replays key downs through a keymap scope; everything else is ignored.
+pub struct KeymapTarget {
+    pub keymap: Keymap,
+    pub scope: KeymapScope,
+}
+impl ReplayTarget for KeymapTarget {
+    fn replay(&mut self, input: &RecordedInput) -> bool {
+        match input {
+            RecordedInput::KeyDown(press) => !matches!(
+                self.keymap.dispatch(press.clone(), &self.scope),
+                KeymapOutcome::NoMatch
+            ),
+            _ => false,
+        }
+    }
+}
+/// Replay against several targets, e.g. a view's handlers and the keymap, stopping at the first
+/// one that handles each input.
+impl<A: ReplayTarget, B: ReplayTarget> ReplayTarget for (A, B) {
+    fn replay(&mut self, input: &RecordedInput) -> bool {
+        self.0.replay(input) || self.1.replay(input)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+struct HeadlessNode {
+    parent: Option<NodeId>,
+    depth: usize,
+    /// In window coordinates
+    bounds: Rect,
+    handlers: EventHandlers,
+    focusable: bool,
+}
+
+/// A stand-in for a window to replay input against: a tree of views given by their bounds and
+/// their handlers. Like a window, it sends pointer events to the innermost view under the
+/// pointer in that view's coordinates and bubbles them up through its parents until one is
+/// handled, sends a click to the views the pointer went down and up on, moves the focus to the
+/// focusable view pressed on and sends key presses to the focused view.
+#[derive(Default)]
+pub struct HeadlessTree {
+    nodes: Vec<HeadlessNode>,
+    focus: Option<NodeId>,
+    pressed: Option<NodeId>,
+}
+impl HeadlessTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a view covering `bounds`, in window coordinates. Views added later are on top of
+    /// earlier ones; overlays such as popovers are added without a parent.
+    pub fn add(&mut self, parent: Option<NodeId>, bounds: Rect, handlers: EventHandlers) -> NodeId {
+        let depth = parent.map_or(0, |parent| self.nodes[parent.0].depth + 1);
+        self.nodes.push(HeadlessNode {
+            parent,
+            depth,
+            bounds,
+            handlers,
+            focusable: false,
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Let `node` take the focus, like `keyboard_navigatable`.
+    pub fn set_focusable(&mut self, node: NodeId) {
+        self.nodes[node.0].focusable = true;
+    }
+
+    pub fn focused(&self) -> Option<NodeId> {
+        self.focus
+    }
+
+    pub fn request_focus(&mut self, node: NodeId) {
+        self.focus = Some(node);
+    }
+
+    /// The innermost, topmost view containing `pos`.
+    fn hit(&self, pos: Point) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.bounds.contains(pos))
+            .max_by_key(|(idx, node)| (node.depth, *idx))
+            .map(|(idx, _)| NodeId(idx))
+    }
+
+    /// `node` followed by its ancestors.
+    fn chain(&self, node: NodeId) -> Vec<NodeId> {
+        let mut chain = vec![node];
+        while let Some(parent) = self.nodes[chain[chain.len() - 1].0].parent {
+            chain.push(parent);
+        }
+        chain
+    }
+
+    fn bubble(&self, chain: &[NodeId], listener: EventListener, event: &Event) -> bool {
+        chain.iter().any(|node| {
+            let node = &self.nodes[node.0];
+            node.handlers
+                .handle(listener, &local_event(event, node.bounds.origin()))
+        })
+    }
+
+    /// Route a pointer or window event like a window would. Returns whether a view handled it.
+    pub fn dispatch(&mut self, event: &Event) -> bool {
+        if let Event::WindowResized(_) = event {
+            let mut handled = false;
+            for node in &self.nodes {
+                handled |= node.handlers.handle(EventListener::WindowResized, event);
+            }
+            return handled;
+        }
+        let Some(chain) = event
+            .point()
+            .and_then(|pos| self.hit(pos))
+            .map(|hit| self.chain(hit))
+        else {
+            return false;
+        };
+        match event {
+            Event::PointerDown(_) => {
+                self.pressed = Some(chain[0]);
+                if let Some(node) = chain.iter().find(|node| self.nodes[node.0].focusable) {
+                    self.focus = Some(*node);
+                }
+                self.bubble(&chain, EventListener::PointerDown, event)
+            }
+            Event::PointerUp(_) => {
+                let handled = self.bubble(&chain, EventListener::PointerUp, event);
+                let pressed = self
+                    .pressed
+                    .take()
+                    .map(|pressed| self.chain(pressed))
+                    .unwrap_or_default();
+                let clicked: Vec<NodeId> = chain
+                    .into_iter()
+                    .filter(|node| pressed.contains(node))
+                    .collect();
+                self.bubble(&clicked, EventListener::Click, event) || handled
+            }
+            Event::PointerMove(_) => self.bubble(&chain, EventListener::PointerMove, event),
+            Event::PointerWheel(_) => self.bubble(&chain, EventListener::PointerWheel, event),
+            _ => false,
+        }
+    }
+
+    /// Send a key press to the focused view and its parents.
+    pub fn key_press(&self, listener: EventListener, press: &KeyPress) -> bool {
+        let Some(focus) = self.focus else {
+            return false;
+        };
+        self.chain(focus).iter().any(|node| {
+            self.nodes[node.0]
+                .handlers
+                .key_presses
+                .get(&listener)
+                .is_some_and(|handler| handler(press))
+        })
+    }
+}
+impl ReplayTarget for HeadlessTree {
+    fn replay(&mut self, input: &RecordedInput) -> bool {
+        match input {
+            RecordedInput::KeyDown(press) | RecordedInput::KeyUp(press) => {
+                self.key_press(input.listener(), press)
+            }
+            _ => input.to_event().is_some_and(|event| self.dispatch(&event)),
+        }
+    }
+}
+
+/// `event` in the coordinates of a view at `origin`.
+fn local_event(event: &Event, origin: Point) -> Event {
+    let offset = origin.to_vec2();
+    match event {
+        Event::PointerDown(pointer) => Event::PointerDown(PointerInputEvent {
+            pos: pointer.pos - offset,
+            ..pointer.clone()
+        }),
+        Event::PointerUp(pointer) => Event::PointerUp(PointerInputEvent {
+            pos: pointer.pos - offset,
+            ..pointer.clone()
+        }),
+        Event::PointerMove(pointer) => Event::PointerMove(PointerMoveEvent {
+            pos: pointer.pos - offset,
+            ..pointer.clone()
+        }),
+        Event::PointerWheel(wheel) => Event::PointerWheel(PointerWheelEvent {
+            pos: wheel.pos - offset,
+            ..wheel.clone()
+        }),
+        other => other.clone(),
+    }
+}
+
+/// Collects events as they happen. Cloning shares the same recording.
+#[derive(Clone)]
+pub struct EventRecorder {
+    start: Instant,
+    recording: Rc<RefCell<Recording>>,
+}
+impl Default for EventRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl EventRecorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            recording: Rc::new(RefCell::new(Recording::default())),
+        }
+    }
+
+    pub fn push(&self, event: &Event) {
+        if let Some(input) = RecordedInput::from_event(event) {
+            self.push_input(input);
+        }
+    }
+
+    fn push_input(&self, input: RecordedInput) {
+        self.recording.borrow_mut().events.push(RecordedEvent {
+            at: self.start.elapsed(),
+            input,
+        });
+    }
+
+    pub fn recording(&self) -> Recording {
+        self.recording.borrow().clone()
+    }
+
+    /// Wrap every handler so the events it receives are recorded before it runs.
+    pub fn wrap(&self, mut handlers: EventHandlers) -> EventHandlers {
+        handlers.handlers = handlers
+            .handlers
+            .into_iter()
+            .map(|(listener, handler)| {
+                let recorder = self.clone();
+                let wrapped: Box<dyn Fn(&Event) -> bool> = Box::new(move |event| {
+                    recorder.push(event);
+                    handler(event)
+                });
+                (listener, wrapped)
+            })
+            .collect();
+        let raw: Vec<EventListener> = handlers.handlers.keys().copied().collect();
+        handlers.key_presses = handlers
+            .key_presses
+            .into_iter()
+            .map(|(listener, handler)| {
+                let recorder = self.clone();
+                // the raw handler of the listener already records the event
+                let record = !raw.contains(&listener);
+                let wrapped: Box<dyn Fn(&KeyPress) -> bool> = Box::new(move |press| {
+                    if record {
+                        recorder.push_input(match listener {
+                            EventListener::KeyUp => RecordedInput::KeyUp(press.clone()),
+                            _ => RecordedInput::KeyDown(press.clone()),
+                        });
+                    }
+                    handler(press)
+                });
+                (listener, wrapped)
+            })
+            .collect();
+        handlers
+    }
+}
+
+pub trait ExtRecordEvents {
+    /// Record the pointer, wheel, key and resize events this view receives.
+    fn record_events(self, recorder: EventRecorder) -> Self;
+}
+impl<T: Decorators> ExtRecordEvents for T {
+    fn record_events(mut self, recorder: EventRecorder) -> Self {
+        for listener in [
+            EventListener::PointerDown,
+            EventListener::PointerUp,
+            EventListener::PointerMove,
+            EventListener::PointerWheel,
+            EventListener::KeyDown,
+            EventListener::KeyUp,
+            EventListener::WindowResized,
+        ] {
+            let recorder = recorder.clone();
+            self = self.on_event(listener, move |event| {
+                recorder.push(event);
+                EventPropagation::Continue
+            });
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use floem::reactive::create_rw_signal;
+
+    use super::*;
+    use crate::{
+        dropdown::{DropdownControls, RowHeight, Selection},
+        handlers::debounce,
+    };
+
+    /// Scrolling a list in two bursts, then saving with a chord.
+    const SESSION: &str = "\
+# list scroll session
+0 pointer_down 12.5 8 primary 1 -
+80 pointer_up 12.5 8 primary 1 -
+100 wheel 12.5 8 0 -40 -
+150 wheel 12.5 8 0 -40 -
+200 wheel 12.5 8 0 -40 shift
+600 wheel 12.5 8 0 40 -
+700 key_down Ctrl+K
+750 key_down Ctrl+S
+800 key_up S
+";
+
+    fn counter() -> (Rc<Cell<usize>>, Rc<Cell<usize>>) {
+        let count = Rc::new(Cell::new(0));
+        (count.clone(), count)
+    }
+
+    #[test]
+    fn recording_round_trips() {
+        let recording = Recording::parse(SESSION).unwrap();
+        assert_eq!(recording.events.len(), 9);
+        assert_eq!(recording.events[6].at, Duration::from_millis(700));
+        assert_eq!(
+            recording.events[4].input,
+            RecordedInput::Wheel {
+                pos: Point::new(12.5, 8.),
+                delta: Vec2::new(0., -40.),
+                mods: Modifiers {
+                    shift: true,
+                    ..Default::default()
+                },
+            }
+        );
+        assert_eq!(Recording::parse(&recording.to_string()).unwrap(), recording);
+    }
+
+    #[test]
+    fn parse_errors_name_line_and_field() {
+        let error = |source: &str| Recording::parse(source).unwrap_err();
+
+        assert_eq!(
+            error("# header\n\n0 pointer_down 1 2 middle 1 -"),
+            ParseRecordingError {
+                line: 3,
+                error: ParseEventError::UnknownButton("middle".to_string()),
+            }
+        );
+        assert_eq!(
+            error("0 wheel 1 2 3").error,
+            ParseEventError::MissingField(5)
+        );
+        assert_eq!(
+            error("soon resize 1 2").error,
+            ParseEventError::InvalidField(0)
+        );
+        assert_eq!(
+            error("0 pointer_move 1 2 hyper").error,
+            ParseEventError::InvalidField(4)
+        );
+        assert_eq!(
+            error("0 teleport").error,
+            ParseEventError::UnknownKind("teleport".to_string())
+        );
+        assert_eq!(
+            error("0 key_down Hyper+K").error,
+            ParseEventError::InvalidKey(ParseKeyBindingError::UnknownModifier("hyper".to_string()))
+        );
+    }
+
+    #[test]
+    fn replay_keeps_recorded_timing_and_reaches_key_handlers() {
+        let recording = Recording::parse(SESSION).unwrap();
+        let (clicks, clicked) = counter();
+        let (scrolls, scrolled) = counter();
+        let (releases, released) = counter();
+        let (saves, saved) = counter();
+        let last_delta = Rc::new(Cell::new(0.));
+        let delta = last_delta.clone();
+
+        let handlers = EventHandlers::new()
+            .on_pointerdown(move |_| {
+                clicks.set(clicks.get() + 1);
+                true
+            })
+            .on_pointerwheel(debounce(Duration::from_millis(200), move |event| {
+                if let Event::PointerWheel(wheel) = event {
+                    delta.set(wheel.delta.y);
+                }
+                scrolls.set(scrolls.get() + 1);
+                true
+            }))
+            .on_key_release(move |press| {
+                assert_eq!(press, &"S".parse().unwrap());
+                releases.set(releases.get() + 1);
+                true
+            });
+        let keymap = Keymap::new();
+        keymap
+            .command("save_all", move || saves.set(saves.get() + 1))
+            .bind("Ctrl+K Ctrl+S", KeymapScope::Global, "save_all");
+        let mut target = (
+            handlers,
+            KeymapTarget {
+                keymap,
+                scope: KeymapScope::Global,
+            },
+        );
+
+        recording.replay(&mut target);
+
+        assert_eq!(clicked.get(), 1);
+        // the first burst settles at 400ms, the lone wheel at 600ms is debounced separately
+        assert_eq!(scrolled.get(), 2);
+        assert_eq!(last_delta.get(), 40.);
+        assert_eq!(saved.get(), 1);
+        assert_eq!(released.get(), 1);
+    }
+
+    #[test]
+    fn replay_runs_debounced_calls_between_events() {
+        let recording = Recording::parse("0 wheel 0 0 0 -40 -\n100 wheel 0 0 0 -40 -").unwrap();
+        let (scrolls, scrolled) = counter();
+        let mut handlers =
+            EventHandlers::new().on_pointerwheel(debounce(Duration::from_millis(50), move |_| {
+                scrolls.set(scrolls.get() + 1);
+                true
+            }));
+
+        recording.replay(&mut handlers);
+
+        // 100ms apart is longer than the wait, so both wheels fire
+        assert_eq!(scrolled.get(), 2);
+    }
+
+    /// Open with the arrow, pick the second fruit with the keyboard, type to jump to the third
+    /// and click the last one in the list.
+    const DROPDOWN_SESSION: &str = "\
+0 pointer_down 190 12 primary 1 -
+40 pointer_up 190 12 primary 1 -
+300 key_down Down
+350 key_down Down
+400 key_down Enter
+900 key_down C
+1000 pointer_down 190 12 primary 1 -
+1040 pointer_up 190 12 primary 1 -
+1100 pointer_down 100 97 primary 1 -
+1140 pointer_up 100 97 primary 1 -
+";
+
+    #[test]
+    fn replays_dropdown_session_through_its_views() {
+        let fruits = create_rw_signal(im::vector!["Apple", "Banana", "Cherry", "Date"]);
+        let chosen = create_rw_signal(None);
+        let picks = create_rw_signal(Vec::new());
+        let controls = DropdownControls::new(
+            fruits.read_only(),
+            |fruit: &&'static str| Some(fruit.to_string()),
+            |fruit: &&'static str| fruit.to_string(),
+            |_| true,
+            Selection::Key(chosen),
+            move |fruit| picks.update(|picks| picks.push(fruit)),
+            RowHeight::Fixed(20.),
+        );
+
+        // the main box with its arrow, and the list below it in a popover
+        let mut tree = HeadlessTree::new();
+        let main_box = tree.add(
+            None,
+            Rect::new(0., 0., 200., 24.),
+            controls.main_box_handlers(),
+        );
+        tree.set_focusable(main_box);
+        let toggle = controls.clone();
+        tree.add(
+            Some(main_box),
+            Rect::new(176., 0., 200., 24.),
+            EventHandlers::new().on_click(move |_| {
+                toggle.toggle();
+                true
+            }),
+        );
+        let list = tree.add(None, Rect::new(0., 27., 200., 107.), EventHandlers::new());
+        for idx in 0..4 {
+            let top = 27. + 20. * idx as f64;
+            tree.add(
+                Some(list),
+                Rect::new(0., top, 200., top + 20.),
+                controls.row_handlers(idx),
+            );
+        }
+
+        let recording = Recording::parse(DROPDOWN_SESSION).unwrap();
+        Recording {
+            events: recording.events[..5].to_vec(),
+        }
+        .replay(&mut tree);
+        assert_eq!(tree.focused(), Some(main_box));
+        assert_eq!(chosen.get_untracked(), Some("Banana".to_string()));
+        assert!(!controls.list.open.get_untracked());
+
+        Recording {
+            events: recording.events[5..].to_vec(),
+        }
+        .replay(&mut tree);
+        assert_eq!(chosen.get_untracked(), Some("Date".to_string()));
+        assert_eq!(picks.get_untracked(), vec!["Banana", "Cherry", "Date"]);
+        assert!(!controls.list.open.get_untracked());
+    }
+}
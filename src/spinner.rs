@@ -0,0 +1,329 @@
+use std::fmt;
+
+use floem::{
+    event::{Event, EventListener},
+    keyboard::{Key, NamedKey},
+    reactive::{create_effect, create_rw_signal, ReadSignal, RwSignal},
+    style::{Background, BorderColor, Display, TextColor},
+    view::View,
+    views::{h_stack, label, text_input, v_stack, Decorators},
+    EventPropagation,
+};
+
+use crate::{
+    dropdown::{stepper_buttons, DPStyles, DropDownStyles},
+    style::{DesignSystem, ExtDynamicColor},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberMode {
+    Int,
+    /// Values are rounded to this many decimals
+    Float {
+        decimals: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpinnerOptions {
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+    /// Used with Shift held and for PageUp/PageDown
+    pub large_step: f64,
+    pub mode: NumberMode,
+    /// Shown after the value, e.g. "px", "pt" or "%". Typing it is optional.
+    pub unit: Option<&'static str>,
+}
+impl SpinnerOptions {
+    pub fn int(min: i64, max: i64) -> Self {
+        Self {
+            min: min as f64,
+            max: max as f64,
+            step: 1.,
+            large_step: 10.,
+            mode: NumberMode::Int,
+            unit: None,
+        }
+    }
+
+    pub fn float(min: f64, max: f64, decimals: usize) -> Self {
+        let step = 10f64.powi(-(decimals as i32));
+        Self {
+            min,
+            max,
+            step,
+            large_step: step * 10.,
+            mode: NumberMode::Float { decimals },
+            unit: None,
+        }
+    }
+
+    pub fn step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn large_step(mut self, large_step: f64) -> Self {
+        self.large_step = large_step;
+        self
+    }
+
+    pub fn unit(mut self, unit: &'static str) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// The text shown for `value`, without the unit.
+    pub fn format(&self, value: f64) -> String {
+        match self.mode {
+            NumberMode::Int => format!("{}", value.round() as i64),
+            NumberMode::Float { decimals } => format!("{value:.decimals$}"),
+        }
+    }
+
+    /// Parse typed text, with or without the unit in any case, into a value within the bounds.
+    pub fn parse(&self, text: &str) -> Result<f64, SpinnerError> {
+        let text = text.trim();
+        let text = self
+            .unit
+            .and_then(|unit| {
+                let split = text.len().checked_sub(unit.len())?;
+                let suffix = text.get(split..)?;
+                suffix.eq_ignore_ascii_case(unit).then(|| &text[..split])
+            })
+            .unwrap_or(text)
+            .trim();
+        let value: f64 = text.parse().map_err(|_| SpinnerError::NotANumber)?;
+        if !value.is_finite() {
+            return Err(SpinnerError::NotANumber);
+        }
+        if self.mode == NumberMode::Int && value.fract() != 0. {
+            return Err(SpinnerError::NotAnInteger);
+        }
+        if value < self.min {
+            return Err(SpinnerError::BelowMin(self.min));
+        }
+        if value > self.max {
+            return Err(SpinnerError::AboveMax(self.max));
+        }
+        Ok(self.round(value))
+    }
+
+    fn round(&self, value: f64) -> f64 {
+        match self.mode {
+            NumberMode::Int => value.round(),
+            NumberMode::Float { decimals } => {
+                let scale = 10f64.powi(decimals as i32);
+                (value * scale).round() / scale
+            }
+        }
+    }
+
+    fn offset(&self, value: f64, by: f64) -> f64 {
+        self.round(value + by).clamp(self.min, self.max)
+    }
+}
+
+/// Why the typed text of a [`spinner`] isn't a valid value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpinnerError {
+    NotANumber,
+    NotAnInteger,
+    BelowMin(f64),
+    AboveMax(f64),
+}
+impl fmt::Display for SpinnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotANumber => write!(f, "Not a number"),
+            Self::NotAnInteger => write!(f, "Must be a whole number"),
+            Self::BelowMin(min) => write!(f, "Must be at least {min}"),
+            Self::AboveMax(max) => write!(f, "Must be at most {max}"),
+        }
+    }
+}
+
+/// An editable number with up/down buttons that repeat while held. The arrow keys and the
+/// scroll wheel step the value, PageUp/PageDown and Shift take a large step. Typed text is
+/// checked as it changes and committed on Enter or when focus leaves; invalid text is flagged
+/// and reverted on commit, Escape reverts right away.
+pub fn spinner<DS: DesignSystem + 'static>(
+    value: RwSignal<f64>,
+    options: SpinnerOptions,
+    styles: ReadSignal<DropDownStyles>,
+) -> impl View {
+    let text = create_rw_signal(options.format(value.get_untracked()));
+    let error: RwSignal<Option<SpinnerError>> = create_rw_signal(None);
+
+    create_effect(move |_| {
+        let formatted = options.format(value.get());
+        text.set(formatted);
+    });
+    create_effect(move |_| {
+        let result = text.with(|text| options.parse(text));
+        error.set(result.err());
+    });
+
+    let revert = move || text.set(options.format(value.get_untracked()));
+    let commit = move || match text.with_untracked(|text| options.parse(text)) {
+        Ok(new) => {
+            value.set(new);
+            text.set(options.format(new));
+        }
+        Err(_) => revert(),
+    };
+    // step from the typed value when it's valid so edits aren't lost
+    let step_by = move |by: f64| {
+        let from = text
+            .with_untracked(|text| options.parse(text))
+            .unwrap_or_else(|_| value.get_untracked());
+        let new = options.offset(from, by);
+        value.set(new);
+        text.set(options.format(new));
+    };
+
+    let input = text_input(text)
+        .keyboard_navigatable()
+        .on_event(EventListener::KeyDown, move |event| {
+            let Event::KeyDown(key_event) = event else {
+                return EventPropagation::Continue;
+            };
+            let step = if key_event.modifiers.shift_key() {
+                options.large_step
+            } else {
+                options.step
+            };
+            match &key_event.key.logical_key {
+                Key::Named(NamedKey::ArrowUp) => step_by(step),
+                Key::Named(NamedKey::ArrowDown) => step_by(-step),
+                Key::Named(NamedKey::PageUp) => step_by(options.large_step),
+                Key::Named(NamedKey::PageDown) => step_by(-options.large_step),
+                Key::Named(NamedKey::Enter) => commit(),
+                Key::Named(NamedKey::Escape) => revert(),
+                _ => return EventPropagation::Continue,
+            }
+            EventPropagation::Stop
+        })
+        .on_event(EventListener::FocusLost, move |_| {
+            commit();
+            EventPropagation::Continue
+        })
+        .style(|s| s.flex_grow(1.).min_width(0.));
+
+    let unit = label(move || options.unit.unwrap_or_default().to_string()).style(move |s| {
+        s.padding_left(DS::PADDING / 4.)
+            .ld_color(TextColor, DS::TEXT.base.set_alpha(60))
+            .apply_if(options.unit.is_none(), |s| s.display(Display::None))
+    });
+
+    let main_box = h_stack((
+        h_stack((input, unit)).style(|s| {
+            s.flex_grow(1.)
+                .items_center()
+                .padding_horiz(DPStyles::SCROLL_CONTAINER_PADDING)
+        }),
+        // the up chevron sends -1 like the dropdown list, where up is towards the start
+        stepper_buttons::<DS>(styles, move |step| step_by(-(step as f64) * options.step)),
+    ))
+    .on_event(EventListener::PointerWheel, move |event| {
+        let Event::PointerWheel(wheel) = event else {
+            return EventPropagation::Continue;
+        };
+        if wheel.delta.y < 0. {
+            step_by(options.step);
+        } else if wheel.delta.y > 0. {
+            step_by(-options.step);
+        }
+        EventPropagation::Stop
+    })
+    .style(move |s| {
+        let styles = styles.get();
+        s.items_center()
+            .width_full()
+            .border(DS::BORDER_SMALL)
+            .border_radius(DS::BORDER_RADIUS)
+            .ld_color(BorderColor, styles.main_box_border)
+            .ld_color(Background, styles.main_box_back)
+            .apply_if(error.with(|error| error.is_some()), |s| {
+                s.ld_color(BorderColor, DS::DANGER.base)
+            })
+    });
+
+    let message = label(move || {
+        error
+            .get()
+            .map(|error| error.to_string())
+            .unwrap_or_default()
+    })
+    .style(move |s| {
+        s.font_size(DS::BASE_FONT_SIZE * 0.85)
+            .ld_color(TextColor, DS::DANGER.base)
+            .apply_if(error.with(|error| error.is_none()), |s| {
+                s.display(Display::None)
+            })
+    });
+
+    v_stack((main_box, message)).style(|s| s.width_full().gap(0., 2.))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_with_or_without_the_unit() {
+        let options = SpinnerOptions::int(0, 100).unit("px");
+        assert_eq!(options.parse("10"), Ok(10.));
+        assert_eq!(options.parse(" 10px "), Ok(10.));
+        assert_eq!(options.parse("10 PX"), Ok(10.));
+        assert_eq!(options.parse("10Px"), Ok(10.));
+        assert_eq!(options.parse("px"), Err(SpinnerError::NotANumber));
+        assert_eq!(options.parse("10pt"), Err(SpinnerError::NotANumber));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let options = SpinnerOptions::int(-5, 5);
+        assert_eq!(options.parse(""), Err(SpinnerError::NotANumber));
+        assert_eq!(options.parse("inf"), Err(SpinnerError::NotANumber));
+        assert_eq!(options.parse("NaN"), Err(SpinnerError::NotANumber));
+        assert_eq!(options.parse("1.5"), Err(SpinnerError::NotAnInteger));
+        assert_eq!(options.parse("-6"), Err(SpinnerError::BelowMin(-5.)));
+        assert_eq!(options.parse("6"), Err(SpinnerError::AboveMax(5.)));
+        assert_eq!(options.parse("-5"), Ok(-5.));
+    }
+
+    #[test]
+    fn rounds_floats_to_their_decimals() {
+        let options = SpinnerOptions::float(0., 10., 2);
+        assert_eq!(options.parse("1.005"), Ok(1.0));
+        assert_eq!(options.parse("1.236"), Ok(1.24));
+        assert_eq!(options.format(1.), "1.00");
+        assert_eq!(options.format(2.345_6), "2.35");
+        assert_eq!(SpinnerOptions::int(0, 10).format(2.6), "3");
+    }
+
+    #[test]
+    fn steps_follow_the_decimals() {
+        let options = SpinnerOptions::float(0., 1., 1);
+        assert_eq!(options.step, 0.1);
+        assert_eq!(options.large_step, 1.);
+        assert_eq!(options.step(0.5).large_step(0.25).large_step, 0.25);
+        let ints = SpinnerOptions::int(0, 100);
+        assert_eq!((ints.step, ints.large_step), (1., 10.));
+    }
+
+    #[test]
+    fn offsets_stay_precise_and_within_bounds() {
+        let options = SpinnerOptions::float(0., 1., 1);
+        assert_eq!(options.offset(0.2, options.step), 0.3);
+        assert_eq!(options.offset(0.7, 0.1 + 0.2), 1.);
+        assert_eq!(options.offset(0.9, options.large_step), 1.);
+        assert_eq!(options.offset(0.1, -options.large_step), 0.);
+
+        let ints = SpinnerOptions::int(-10, 10);
+        assert_eq!(ints.offset(9., ints.large_step), 10.);
+        assert_eq!(ints.offset(-3., -ints.step), -4.);
+    }
+}
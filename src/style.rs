@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, ops::Deref};
+use std::{marker::PhantomData, ops::Deref, rc::Rc};
 
 use colorsys::ColorAlpha;
 use floem::{
@@ -12,6 +12,8 @@ use floem::{
 };
 use paste::paste;
 
+use crate::keymap::KeyPress;
+
 macro_rules! generate_hsl_methods {
     ($($field:ident),*) => {
         $(
@@ -52,7 +54,7 @@ macro_rules! generate_hsl_methods {
     };
 }
 
-#[derive(Clone, Copy, Default, Debug)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
 pub struct HSLColor {
     hue: u8,
     sat: u8,
@@ -115,6 +117,79 @@ impl HSLColor {
         self
     }
 
+    pub const fn hue(self) -> u8 {
+        self.hue
+    }
+
+    pub const fn sat(self) -> u8 {
+        self.sat
+    }
+
+    pub const fn light(self) -> u8 {
+        self.light
+    }
+
+    pub const fn alpha(self) -> u8 {
+        self.alpha
+    }
+
+    /// Channels 0-255, alpha 0-100
+    pub fn from_rgb(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        let rgb = colorsys::Rgb::new(red as f64, green as f64, blue as f64, None);
+        let hsl = colorsys::Hsl::from(rgb);
+        Self {
+            hue: (hsl.get_hue() / 3.6).round() as u8,
+            sat: hsl.get_saturation().round() as u8,
+            light: hsl.get_lightness().round() as u8,
+            alpha: alpha.min(100),
+        }
+    }
+
+    /// Channels 0-255, without alpha
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        let rgb = colorsys::Rgb::from(colorsys::Hsl::from(self));
+        (
+            rgb.red().round() as u8,
+            rgb.green().round() as u8,
+            rgb.blue().round() as u8,
+        )
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` when not fully opaque
+    pub fn to_hex(self) -> String {
+        let (red, green, blue) = self.to_rgb();
+        if self.alpha >= 100 {
+            format!("#{red:02x}{green:02x}{blue:02x}")
+        } else {
+            let alpha = (self.alpha as f64 * 2.55).round() as u8;
+            format!("#{red:02x}{green:02x}{blue:02x}{alpha:02x}")
+        }
+    }
+
+    /// Parse `#rgb`, `#rrggbb` or `#rrggbbaa`, the `#` is optional.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().trim_start_matches('#');
+        if !hex.is_ascii() {
+            return None;
+        }
+        let channel = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+        let (red, green, blue, alpha) = match hex.len() {
+            3 => {
+                let short = |idx: usize| {
+                    u8::from_str_radix(hex.get(idx..idx + 1)?, 16)
+                        .ok()
+                        .map(|val| val * 17)
+                };
+                (short(0)?, short(1)?, short(2)?, 255)
+            }
+            6 => (channel(0)?, channel(2)?, channel(4)?, 255),
+            8 => (channel(0)?, channel(2)?, channel(4)?, channel(6)?),
+            _ => return None,
+        };
+        let alpha = (alpha as f64 / 2.55).round() as u8;
+        Some(Self::from_rgb(red, green, blue, alpha))
+    }
+
     // pub const fn fg_color(self) -> Self {
     //     if self.light < 50 {
     //         ColorPalette::LIGHT1
@@ -156,6 +231,9 @@ impl From<HSLColor> for colorsys::Hsl {
     }
 }
 
+/// A global trigger the app notifies on every window click. New code should use
+/// [`ExtClickOutside::on_click_outside`](crate::click_outside::ExtClickOutside::on_click_outside),
+/// which needs no app wiring and ignores clicks inside owned overlays.
 #[derive(Clone, Copy)]
 pub struct WindowClicked(pub Trigger);
 impl Deref for WindowClicked {
@@ -174,6 +252,8 @@ pub type DarkMode = bool;
 
 pub type BorderRadiusPercent = f32;
 
+/// A single trigger that closes every popover following it. Nested popovers should use the
+/// stack in [`crate::popover`] instead, which only closes the topmost one.
 pub type PopOver = Trigger;
 pub fn follow_popover(visible_state: RwSignal<bool>) {
     let pop_over = use_context::<PopOver>().unwrap();
@@ -202,6 +282,20 @@ pub trait DesignSystem {
 
     const TRANSPARENT: Color = Color::TRANSPARENT;
 
+    // status colors
+    const SUCCESS: ResponsiveColor = ResponsiveColor::from_lightdark(LightDark::new(
+        HSLColor::new(38, 55, 40, 100),
+        HSLColor::new(38, 45, 45, 100),
+    ));
+    const WARNING: ResponsiveColor = ResponsiveColor::from_lightdark(LightDark::new(
+        HSLColor::new(11, 90, 45, 100),
+        HSLColor::new(11, 80, 50, 100),
+    ));
+    const DANGER: ResponsiveColor = ResponsiveColor::from_lightdark(LightDark::new(
+        HSLColor::new(0, 70, 48, 100),
+        HSLColor::new(0, 65, 55, 100),
+    ));
+
     // computed colors
     const BACKGROUND_2: ResponsiveColor = ResponsiveColor::from_lightdark(
         Self::BACKGROUND
@@ -222,6 +316,12 @@ pub trait DesignSystem {
             .dark_decrease_cap_light(10),
     );
 
+    /// Dims the window behind modal surfaces. Dark in both modes.
+    const BACKDROP: LightDark = LightDark::new(
+        Self::BACKGROUND.base.dark.set_alpha(55),
+        Self::BACKGROUND.base.dark.set_alpha(65),
+    );
+
     const HOVER_BACKGROUND: ResponsiveColor = ResponsiveColor {
         base: LightDark::transparent(),
         hover: Self::BACKGROUND.base,
@@ -321,7 +421,8 @@ impl LightDark {
         self
     }
 
-    fn get_base(self) -> HSLColor {
+    /// The color for the current light or dark mode
+    pub fn get_base(self) -> HSLColor {
         let dark_mode = use_context::<RwSignal<DarkMode>>().unwrap();
         match (dark_mode.get(), self.light_mode_default) {
             (true, LightModeDefault::Light) => self.dark,
@@ -404,6 +505,10 @@ pub trait ExtAnyEvent {
             Box<dyn Fn(&Event) -> floem::EventPropagation + 'static>,
         >,
     ) -> Self;
+
+    /// Attach every handler of `handlers`, including its key press handlers. Handled events stop
+    /// propagating.
+    fn event_handlers(self, handlers: EventHandlers) -> Self;
 }
 impl<T: View + Decorators> ExtAnyEvent for T {
     fn all_events(
@@ -418,10 +523,34 @@ impl<T: View + Decorators> ExtAnyEvent for T {
         }
         self
     }
+
+    fn event_handlers(mut self, handlers: EventHandlers) -> Self {
+        let mut listeners: Vec<EventListener> = handlers.handlers.keys().copied().collect();
+        for listener in handlers.key_presses.keys() {
+            if !listeners.contains(listener) {
+                listeners.push(*listener);
+            }
+        }
+        let handlers = Rc::new(handlers);
+        for listener in listeners {
+            let handlers = handlers.clone();
+            self = self.on_event(listener, move |event| {
+                if handlers.handle(listener, event) {
+                    floem::EventPropagation::Stop
+                } else {
+                    floem::EventPropagation::Continue
+                }
+            });
+        }
+        self
+    }
 }
 // Define a custom data structure to hold event handlers
 pub struct EventHandlers {
     pub handlers: std::collections::HashMap<EventListener, Box<dyn Fn(&Event) -> bool + 'static>>,
+    /// Key handlers for `KeyDown` and `KeyUp` that take the press rather than the raw event
+    pub key_presses:
+        std::collections::HashMap<EventListener, Box<dyn Fn(&KeyPress) -> bool + 'static>>,
     _x: PhantomData<()>,
 }
 macro_rules! add_handler {
@@ -464,9 +593,48 @@ impl EventHandlers {
     pub fn new() -> Self {
         Self {
             handlers: std::collections::HashMap::new(),
+            key_presses: std::collections::HashMap::new(),
             _x: PhantomData,
         }
     }
+
+    /// Handle key downs as [`KeyPress`]es. Unlike `on_keydown`, this also receives the key
+    /// presses of a replayed [`Recording`](crate::record::Recording), as floem key events can't
+    /// be built outside the windowing backend.
+    pub fn on_key_press(mut self, action: impl Fn(&KeyPress) -> bool + 'static) -> Self {
+        self.key_presses
+            .insert(EventListener::KeyDown, Box::new(action));
+        self
+    }
+
+    /// Like [`EventHandlers::on_key_press`], for key ups.
+    pub fn on_key_release(mut self, action: impl Fn(&KeyPress) -> bool + 'static) -> Self {
+        self.key_presses
+            .insert(EventListener::KeyUp, Box::new(action));
+        self
+    }
+
+    /// Run the handlers `listener` has for `event`. Returns whether one of them handled it.
+    pub fn handle(&self, listener: EventListener, event: &Event) -> bool {
+        if self
+            .handlers
+            .get(&listener)
+            .is_some_and(|handler| handler(event))
+        {
+            return true;
+        }
+        let press = match event {
+            Event::KeyDown(_) => KeyPress::from_event(event),
+            Event::KeyUp(key_event) => {
+                KeyPress::from_key(&key_event.key.logical_key, key_event.modifiers.into())
+            }
+            _ => None,
+        };
+        match (press, self.key_presses.get(&listener)) {
+            (Some(press), Some(handler)) => handler(&press),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -584,3 +752,87 @@ pub fn lazy_size_and_rad() -> (
         move || border_radius(rect_sig.get()),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXACT: [(u8, u8, u8); 5] = [
+        (255, 0, 0),
+        (0, 255, 255),
+        (255, 255, 255),
+        (128, 128, 128),
+        (0, 0, 0),
+    ];
+
+    #[test]
+    fn rgb_round_trips() {
+        for (red, green, blue) in EXACT {
+            let color = HSLColor::from_rgb(red, green, blue, 100);
+            assert_eq!(color.to_rgb(), (red, green, blue));
+        }
+    }
+
+    #[test]
+    fn rgb_round_trip_stays_within_hue_resolution() {
+        // hue is kept in steps of 3.6°, so saturated colors can move a few levels
+        for (red, green, blue) in [(0, 255, 0), (51, 102, 153), (255, 128, 0), (170, 187, 204)] {
+            let (r, g, b) = HSLColor::from_rgb(red, green, blue, 100).to_rgb();
+            for (before, after) in [(red, r), (green, g), (blue, b)] {
+                assert!(before.abs_diff(after) <= 6, "{red} {green} {blue}");
+            }
+        }
+    }
+
+    #[test]
+    fn from_rgb_converts_to_percentages() {
+        let red = HSLColor::from_rgb(255, 0, 0, 150);
+        assert_eq!(red, HSLColor::new(0, 100, 50, 100));
+        let cyan = HSLColor::from_rgb(0, 255, 255, 40);
+        assert_eq!(cyan, HSLColor::new(50, 100, 50, 40));
+    }
+
+    #[test]
+    fn hue_wraps_at_360_degrees() {
+        assert_eq!(HSLColor::new(100, 100, 50, 100).to_rgb(), (255, 0, 0));
+        // just below 360°
+        assert_eq!(HSLColor::from_rgb(255, 0, 4, 100).hue(), 100);
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        for hex in ["#ff0000", "#00ffff", "#ffffff", "#808080", "#000000"] {
+            assert_eq!(HSLColor::from_hex(hex).unwrap().to_hex(), hex);
+        }
+        for hex in ["#ff000080", "#00ffff00", "#ffffff1a"] {
+            assert_eq!(HSLColor::from_hex(hex).unwrap().to_hex(), hex);
+        }
+    }
+
+    #[test]
+    fn hex_reads_short_and_long_forms() {
+        let red = HSLColor::from_rgb(255, 0, 0, 100);
+        assert_eq!(HSLColor::from_hex("#f00"), Some(red));
+        assert_eq!(HSLColor::from_hex("F00"), Some(red));
+        assert_eq!(HSLColor::from_hex(" #ff0000 "), Some(red));
+        assert_eq!(HSLColor::from_hex("#ff0000ff"), Some(red));
+        assert_eq!(HSLColor::from_hex("#FFF").unwrap().to_hex(), "#ffffff");
+    }
+
+    #[test]
+    fn hex_alpha() {
+        assert_eq!(HSLColor::from_hex("#fff").unwrap().alpha(), 100);
+        assert_eq!(HSLColor::from_hex("#ffffff").unwrap().alpha(), 100);
+        assert_eq!(HSLColor::from_hex("#ffffff80").unwrap().alpha(), 50);
+        assert_eq!(HSLColor::from_hex("#ffffff00").unwrap().alpha(), 0);
+        assert_eq!(HSLColor::new(0, 100, 50, 50).to_hex(), "#ff000080");
+        assert_eq!(HSLColor::new(0, 100, 50, 100).to_hex(), "#ff0000");
+    }
+
+    #[test]
+    fn hex_rejects_invalid_input() {
+        for hex in ["", "#", "#ff00", "#ff0000f", "#gg0000", "#ffé00"] {
+            assert_eq!(HSLColor::from_hex(hex), None, "{hex}");
+        }
+    }
+}
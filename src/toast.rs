@@ -0,0 +1,313 @@
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Sender;
+use floem::{
+    action::{add_overlay, exec_after, remove_overlay},
+    event::EventListener,
+    ext_event::create_signal_from_channel,
+    id::Id,
+    kurbo::Point,
+    reactive::{create_effect, create_rw_signal, use_context, RwSignal},
+    style::{Background, BorderColor, InsetLeft, TextColor, Transition},
+    view::View,
+    views::{container, h_stack, label, list, Decorators},
+    EventPropagation,
+};
+
+use crate::{
+    gesture::TimerSlot,
+    icons::icon_x_mark,
+    style::{DesignSystem, ExtDynamicColor, ResponsiveColor},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+impl ToastKind {
+    fn color<DS: DesignSystem>(self) -> ResponsiveColor {
+        match self {
+            ToastKind::Info => DS::ACCENT,
+            ToastKind::Success => DS::SUCCESS,
+            ToastKind::Warning => DS::WARNING,
+            ToastKind::Error => DS::DANGER,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// A notification. Built with [`Toast::new`] and sent with [`Toaster::push`] or [`notify`].
+#[derive(Clone)]
+pub struct Toast {
+    pub kind: ToastKind,
+    pub message: String,
+    /// `None` keeps the toast until it is closed
+    pub duration: Option<Duration>,
+    pub actions: Vec<(String, Rc<dyn Fn()>)>,
+}
+impl Toast {
+    pub fn new(kind: ToastKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            duration: Some(Duration::from_secs(5)),
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn duration(mut self, duration: Option<Duration>) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Add a button. Choosing it runs `action` and closes the toast.
+    pub fn action(mut self, label: impl Into<String>, action: impl Fn() + 'static) -> Self {
+        self.actions.push((label.into(), Rc::new(action)));
+        self
+    }
+}
+
+/// Plain data for toasts sent from other threads, e.g. tokio tasks spawned through
+/// `floem_tokio`. Actions can't cross threads, so these toasts have none.
+#[derive(Clone, Debug)]
+pub struct ToastMessage {
+    pub kind: ToastKind,
+    pub message: String,
+}
+
+#[derive(Clone)]
+struct ShownToast {
+    key: u64,
+    toast: Toast,
+    entered: RwSignal<bool>,
+    leaving: RwSignal<bool>,
+}
+
+/// The toast stack. Provide one at the root of the app with
+/// `provide_context(Toaster::new::<MyDesign>(Corner::BottomRight))`, then call [`notify`] from
+/// anywhere below it.
+#[derive(Clone, Copy)]
+pub struct Toaster {
+    toasts: RwSignal<im::Vector<ShownToast>>,
+    next_key: RwSignal<u64>,
+    overlay: RwSignal<Option<Id>>,
+    sender: RwSignal<Sender<ToastMessage>>,
+}
+
+const EXIT_ANIMATION: Duration = Duration::from_millis(200);
+
+impl Toaster {
+    pub fn new<DS: DesignSystem + 'static>(corner: Corner) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let toaster = Self {
+            toasts: create_rw_signal(im::Vector::new()),
+            next_key: create_rw_signal(0),
+            overlay: create_rw_signal(None),
+            sender: create_rw_signal(tx),
+        };
+
+        let received = create_signal_from_channel(rx);
+        create_effect(move |_| {
+            if let Some(message) = received.get() {
+                toaster.push(Toast::new(message.kind, message.message));
+            }
+        });
+
+        // the overlay only exists while there is something to show
+        create_effect(move |_| {
+            let empty = toaster.toasts.with(|toasts| toasts.is_empty());
+            match (empty, toaster.overlay.get_untracked()) {
+                (false, None) => {
+                    let id = add_overlay(Point::ZERO, move |_| toast_stack::<DS>(toaster, corner));
+                    toaster.overlay.set(Some(id));
+                }
+                (true, Some(id)) => {
+                    remove_overlay(id);
+                    toaster.overlay.set(None);
+                }
+                _ => {}
+            }
+        });
+        toaster
+    }
+
+    /// A sender that can be moved to another thread. Messages show up as toasts on the UI thread.
+    pub fn sender(&self) -> Sender<ToastMessage> {
+        self.sender.get_untracked()
+    }
+
+    pub fn push(&self, toast: Toast) {
+        let key = self.next_key.get_untracked();
+        self.next_key.set(key + 1);
+        let entered = create_rw_signal(false);
+        self.toasts.update(|toasts| {
+            toasts.push_back(ShownToast {
+                key,
+                toast,
+                entered,
+                leaving: create_rw_signal(false),
+            })
+        });
+        // flip after the first layout so the enter transition runs
+        exec_after(Duration::from_millis(16), move |_| entered.set(true));
+    }
+
+    /// Play the exit animation, then remove the toast.
+    fn close(&self, key: u64) {
+        let Some(leaving) = self.toasts.with_untracked(|toasts| {
+            toasts
+                .iter()
+                .find(|shown| shown.key == key)
+                .map(|shown| shown.leaving)
+        }) else {
+            return;
+        };
+        leaving.set(true);
+        let toasts = self.toasts;
+        exec_after(EXIT_ANIMATION, move |_| {
+            toasts.update(|toasts| toasts.retain(|shown| shown.key != key));
+        });
+    }
+}
+
+/// Show a toast through the [`Toaster`] in context.
+pub fn notify(kind: ToastKind, message: impl Into<String>) {
+    let toaster = use_context::<Toaster>().expect("Expected a Toaster to have been provided");
+    toaster.push(Toast::new(kind, message));
+}
+
+fn toast_stack<DS: DesignSystem + 'static>(toaster: Toaster, corner: Corner) -> impl View {
+    let from_left = matches!(corner, Corner::TopLeft | Corner::BottomLeft);
+    let from_top = matches!(corner, Corner::TopLeft | Corner::TopRight);
+    let toasts = list(
+        move || {
+            let toasts = toaster.toasts.get();
+            // newest closest to the corner
+            if from_top {
+                toasts.into_iter().rev().collect::<im::Vector<_>>()
+            } else {
+                toasts
+            }
+        },
+        |shown| shown.key,
+        move |shown| toast_view::<DS>(toaster, shown, from_left),
+    )
+    .style(|s| s.flex_col().gap(0., DS::PADDING));
+
+    // a window sized layer so the stack can sit in a corner; it has no handlers of its own so
+    // clicks next to the toasts reach the app
+    container(toasts).style(move |s| {
+        s.size_full()
+            .flex_col()
+            .padding(DS::PADDING * 2.)
+            .apply_if(from_top, |s| s.justify_start())
+            .apply_if(!from_top, |s| s.justify_end())
+            .apply_if(from_left, |s| s.items_start())
+            .apply_if(!from_left, |s| s.items_end())
+    })
+}
+
+fn toast_view<DS: DesignSystem + 'static>(
+    toaster: Toaster,
+    shown: ShownToast,
+    from_left: bool,
+) -> impl View {
+    let ShownToast {
+        key,
+        toast,
+        entered,
+        leaving,
+    } = shown;
+    let color = toast.kind.color::<DS>();
+
+    // auto dismiss, paused while hovered
+    let timer = TimerSlot::default();
+    let remaining = create_rw_signal(toast.duration);
+    let started = create_rw_signal(Instant::now());
+    let start = {
+        let timer = timer.clone();
+        move || {
+            if let Some(left) = remaining.get_untracked() {
+                started.set(Instant::now());
+                timer.start(left, move || toaster.close(key));
+            }
+        }
+    };
+    start();
+    let pause = move || {
+        timer.cancel();
+        if let Some(left) = remaining.get_untracked() {
+            let elapsed = started.get_untracked().elapsed();
+            remaining.set(Some(left.saturating_sub(elapsed)));
+        }
+    };
+
+    let message = toast.message.clone();
+    let actions = toast.actions.clone();
+    let buttons = list(
+        move || actions.clone().into_iter().enumerate(),
+        |(idx, _)| *idx,
+        move |(_, (text, action))| {
+            label(move || text.clone())
+                .on_click_stop(move |_| {
+                    action();
+                    toaster.close(key);
+                })
+                .style(move |s| {
+                    s.padding_horiz(DS::PADDING)
+                        .border_radius(DS::BORDER_RADIUS)
+                        .dynamic_color(Background, DS::HOVER_BACKGROUND)
+                        .ld_color(TextColor, color.base)
+                })
+        },
+    )
+    .style(|s| s.gap(DS::PADDING / 2., 0.));
+
+    let slide = 40.;
+    h_stack((
+        label(move || message.clone()).style(|s| s.flex_grow(1.).max_width(320.)),
+        buttons,
+        icon_x_mark()
+            .on_click_stop(move |_| toaster.close(key))
+            .style(|s| {
+                s.size(DS::BASE_FONT_SIZE * 0.8, DS::BASE_FONT_SIZE * 0.8)
+                    .dynamic_color(TextColor, DS::TEXT.set_hover_to_base())
+            }),
+    ))
+    .on_event(EventListener::PointerEnter, move |_| {
+        pause();
+        EventPropagation::Continue
+    })
+    .on_event(EventListener::PointerLeave, move |_| {
+        start();
+        EventPropagation::Continue
+    })
+    .style(move |s| {
+        let on_screen = entered.get() && !leaving.get();
+        let offset = if from_left { -slide } else { slide };
+        s.items_center()
+            .gap(DS::PADDING, 0.)
+            .min_width(220.)
+            .padding(DS::PADDING)
+            .border_left(DS::BORDER_BIG * 2.)
+            .border_radius(DS::BORDER_RADIUS)
+            .ld_color(BorderColor, color.base)
+            .ld_color(Background, DS::BACKGROUND_EXT.base)
+            .ld_color(TextColor, DS::TEXT.base)
+            .inset_left(if on_screen { 0. } else { offset })
+            .transition(InsetLeft, Transition::linear(EXIT_ANIMATION.as_secs_f64()))
+    })
+}
@@ -0,0 +1,127 @@
+use std::{rc::Rc, time::Duration};
+
+use floem::{
+    event::EventListener,
+    reactive::create_rw_signal,
+    style::{Background, BorderColor, TextColor},
+    view::View,
+    views::{container, label, Decorators},
+    EventPropagation,
+};
+
+use crate::{
+    gesture::TimerSlot,
+    pop_over::{pop_over, Align, Placement, Side},
+    style::{DesignSystem, ExtDynamicColor},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct TooltipConfig {
+    /// How long the pointer has to rest on the anchor before the tooltip shows
+    pub delay: Duration,
+    /// How long the tooltip stays after the pointer leaves, so it can be reached from the anchor
+    pub grace: Duration,
+    pub placement: Placement,
+}
+impl Default for TooltipConfig {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(600),
+            grace: Duration::from_millis(150),
+            placement: Placement::new(Side::Top, Align::Center),
+        }
+    }
+}
+
+/// Show `content` next to `anchor` while the pointer rests on it. Moving from the anchor into the
+/// tooltip keeps it open, so the content can hold links or buttons.
+pub fn tooltip<DS, V, TV>(
+    anchor: V,
+    content: impl Fn() -> TV + 'static,
+    config: TooltipConfig,
+) -> impl View
+where
+    DS: DesignSystem + 'static,
+    V: View + 'static,
+    TV: View + 'static,
+{
+    let visible = create_rw_signal(false);
+    let timer = TimerSlot::default();
+
+    let show = {
+        let timer = timer.clone();
+        move || timer.start(config.delay, move || visible.set(true))
+    };
+    let hide = {
+        let timer = timer.clone();
+        move || timer.start(config.grace, move || visible.set(false))
+    };
+    let keep = {
+        let timer = timer.clone();
+        move || timer.cancel()
+    };
+    let (hide_anchor, hide_content) = (hide.clone(), hide);
+    let keep_anchor = keep.clone();
+
+    let anchor = container(anchor)
+        .on_event(EventListener::PointerEnter, move |_| {
+            if visible.get_untracked() {
+                keep_anchor();
+            } else {
+                show();
+            }
+            EventPropagation::Continue
+        })
+        .on_event(EventListener::PointerLeave, move |_| {
+            hide_anchor();
+            EventPropagation::Continue
+        })
+        .on_event(EventListener::PointerDown, move |_| {
+            timer.cancel();
+            visible.set(false);
+            EventPropagation::Continue
+        });
+
+    let bubble = move || {
+        let keep = keep.clone();
+        let hide = hide_content.clone();
+        container(content())
+            .on_event(EventListener::PointerEnter, move |_| {
+                keep();
+                EventPropagation::Continue
+            })
+            .on_event(EventListener::PointerLeave, move |_| {
+                hide();
+                EventPropagation::Continue
+            })
+            .style(|s| {
+                s.padding_horiz(DS::PADDING)
+                    .padding_vert(DS::PADDING / 2.)
+                    .border(DS::BORDER_SMALL)
+                    .border_radius(DS::BORDER_RADIUS)
+                    .font_size(DS::BASE_FONT_SIZE * 0.9)
+                    .ld_color(Background, DS::BACKGROUND_EXT.base)
+                    .ld_color(BorderColor, DS::BACKGROUND_3.base)
+                    .ld_color(TextColor, DS::TEXT.base)
+            })
+    };
+
+    pop_over(anchor, bubble, visible, config.placement)
+}
+
+/// A [`tooltip`] with plain text.
+pub fn text_tooltip<DS: DesignSystem + 'static, V: View + 'static>(
+    anchor: V,
+    text: impl Fn() -> String + 'static,
+    config: TooltipConfig,
+) -> impl View {
+    let text = Rc::new(text);
+    tooltip::<DS, _, _>(
+        anchor,
+        move || {
+            let text = text.clone();
+            label(move || text())
+        },
+        config,
+    )
+}